@@ -0,0 +1,334 @@
+//! Computer players that steer the snake instead of the keyboard.
+use clap::ValueEnum;
+use std::collections::{HashSet, VecDeque};
+use std::io;
+
+use crate::bot::{Bot, BotCommand};
+use crate::direction::Direction;
+use crate::game::{next_position, Game};
+
+const DIRECTIONS: [Direction; 4] = [
+    Direction::Up,
+    Direction::Right,
+    Direction::Down,
+    Direction::Left,
+];
+
+/// Decides where the snake goes next.
+pub trait Controller {
+    /// Called before every move with the current state of the game. An error gives up
+    /// the game.
+    fn next_direction(&mut self, game: &Game) -> Result<Direction, Forfeit>;
+}
+
+/// Why a controller gave up the game.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Forfeit {
+    /// It did not answer within its time limit.
+    TooSlow,
+    /// It answered with something that is not a direction.
+    InvalidMove,
+    /// It can not be asked any more, usually because its process exited.
+    Gone,
+}
+
+/// Who steers the snake instead of the player.
+#[derive(Clone, Debug)]
+pub enum Autopilot {
+    Strategy(Strategy),
+    Bot(BotCommand),
+}
+
+impl Autopilot {
+    /// Creates a controller for a new game. For a bot, this starts its program.
+    pub fn controller(&self) -> io::Result<Box<dyn Controller>> {
+        match self {
+            Autopilot::Strategy(strategy) => Ok(strategy.controller()),
+            Autopilot::Bot(command) => Ok(Box::new(Bot::spawn(command)?)),
+        }
+    }
+}
+
+/// The built-in controllers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Strategy {
+    /// Takes the shortest path to the food, as long as it does not trap the snake
+    Greedy,
+    /// Follows a cycle through every field of the board, so it never dies, but is slow
+    Hamiltonian,
+}
+
+impl Strategy {
+    pub fn controller(self) -> Box<dyn Controller> {
+        match self {
+            Strategy::Greedy => Box::new(Greedy),
+            Strategy::Hamiltonian => Box::new(Hamiltonian::default()),
+        }
+    }
+}
+
+/// Fields the snake's head can not move into on the next move.
+fn blocked_fields(game: &Game) -> HashSet<(u16, u16)> {
+    let body = game.snake_body();
+    // The tail moves out of the way, unless the snake is about to grow
+    let moving_body = if game.snake_length() > body.len() {
+        body
+    } else {
+        &body[..body.len() - 1]
+    };
+
+    moving_body
+        .iter()
+        .chain(game.obstacles())
+        .copied()
+        .collect()
+}
+
+/// Neighbours of `pos` that are on the board, wrapping around the edges if the walls do.
+fn neighbours(game: &Game, pos: (u16, u16)) -> impl Iterator<Item = (Direction, (u16, u16))> {
+    let (dimensions, walls) = (game.dimensions, game.walls);
+    DIRECTIONS.into_iter().filter_map(move |direction| {
+        next_position(pos, direction, dimensions, walls).map(|next| (direction, next))
+    })
+}
+
+/// Number of free fields that can be reached from `start`.
+fn reachable_area(game: &Game, start: (u16, u16), blocked: &HashSet<(u16, u16)>) -> usize {
+    let mut visited = HashSet::from([start]);
+    let mut queue = VecDeque::from([start]);
+
+    while let Some(pos) = queue.pop_front() {
+        for (_, next) in neighbours(game, pos) {
+            if !blocked.contains(&next) && visited.insert(next) {
+                queue.push_back(next);
+            }
+        }
+    }
+
+    visited.len()
+}
+
+/// Heads for the nearest food or fruit along the shortest path. A move that would leave
+/// the snake less room than its own length is only taken if there is nothing better.
+pub struct Greedy;
+
+impl Greedy {
+    /// First move of the shortest path from the head to the food or the fruit.
+    fn path_to_food(game: &Game, blocked: &HashSet<(u16, u16)>) -> Option<Direction> {
+        let head = game.snake_body()[0];
+        let mut targets = vec![game.food];
+        targets.extend(game.fruit.map(|(fruit, _)| fruit));
+
+        let mut visited = HashSet::from([head]);
+        let mut queue: VecDeque<((u16, u16), Direction)> = neighbours(game, head)
+            .filter(|(_, next)| !blocked.contains(next) && visited.insert(*next))
+            .map(|(direction, next)| (next, direction))
+            .collect();
+
+        while let Some((pos, first_move)) = queue.pop_front() {
+            if targets.contains(&pos) {
+                return Some(first_move);
+            }
+            for (_, next) in neighbours(game, pos) {
+                if !blocked.contains(&next) && visited.insert(next) {
+                    queue.push_back((next, first_move));
+                }
+            }
+        }
+
+        None
+    }
+}
+
+impl Greedy {
+    fn choose(game: &Game) -> Direction {
+        let head = game.snake_body()[0];
+        let blocked = blocked_fields(game);
+        let area_after = |direction: Direction| {
+            next_position(head, direction, game.dimensions, game.walls)
+                .filter(|next| !blocked.contains(next))
+                .map_or(0, |next| reachable_area(game, next, &blocked))
+        };
+
+        if let Some(direction) = Greedy::path_to_food(game, &blocked) {
+            if area_after(direction) >= game.snake_length() {
+                return direction;
+            }
+        }
+
+        // No safe way to the food, so stay alive in the largest free area
+        DIRECTIONS
+            .into_iter()
+            .max_by_key(|direction| area_after(*direction))
+            .unwrap()
+    }
+}
+
+impl Controller for Greedy {
+    fn next_direction(&mut self, game: &Game) -> Result<Direction, Forfeit> {
+        Ok(Greedy::choose(game))
+    }
+}
+
+/// Follows a fixed cycle that visits every field once. Needs an empty board with an even
+/// width or height and the snake lying along the cycle, as it does at the start of a game
+/// on an empty board. On other boards it plays like [`Greedy`].
+#[derive(Default)]
+pub struct Hamiltonian {
+    /// Direction to take on every field in row-major order. Built before the first move,
+    /// the inner `None` means the board has no usable cycle.
+    cycle: Option<Option<Vec<Direction>>>,
+}
+
+impl Hamiltonian {
+    /// Goes down the first column, snakes back and forth through the rest of the board and
+    /// returns along the first row, so the cycle ends behind the snake's starting position.
+    fn build_cycle(game: &Game) -> Option<Vec<Direction>> {
+        let (rows, cols) = game.dimensions;
+        if !game.obstacles().is_empty() || (rows % 2 == 1 && cols % 2 == 1) {
+            return None;
+        }
+
+        let mut order: Vec<(u16, u16)> = (0..rows).map(|row| (row, 0)).collect();
+        if rows % 2 == 0 {
+            // Rows from the bottom up, in alternating directions
+            for (i, row) in (1..rows).rev().enumerate() {
+                let cols_in_order: Vec<u16> = if i % 2 == 0 {
+                    (1..cols).collect()
+                } else {
+                    (1..cols).rev().collect()
+                };
+                order.extend(cols_in_order.into_iter().map(|col| (row, col)));
+            }
+        } else {
+            // Columns from left to right, in alternating directions
+            for (i, col) in (1..cols).enumerate() {
+                let rows_in_order: Vec<u16> = if i % 2 == 0 {
+                    (1..rows).rev().collect()
+                } else {
+                    (1..rows).collect()
+                };
+                order.extend(rows_in_order.into_iter().map(|row| (row, col)));
+            }
+        }
+        order.extend((1..cols).rev().map(|col| (0, col)));
+
+        let index = |pos: (u16, u16)| pos.0 as usize * cols as usize + pos.1 as usize;
+        let mut cycle = vec![Direction::Up; order.len()];
+        for (i, pos) in order.iter().enumerate() {
+            let next = order[(i + 1) % order.len()];
+            cycle[index(*pos)] = direction_between(*pos, next);
+        }
+
+        // Following the cycle is only safe if the snake already lies along it
+        let body = game.snake_body();
+        let follows_cycle = body.windows(2).all(|pair| {
+            let (ahead, behind) = (pair[0], pair[1]);
+            next_position(behind, cycle[index(behind)], game.dimensions, game.walls) == Some(ahead)
+        });
+
+        follows_cycle.then_some(cycle)
+    }
+}
+
+impl Controller for Hamiltonian {
+    fn next_direction(&mut self, game: &Game) -> Result<Direction, Forfeit> {
+        let cycle = self
+            .cycle
+            .get_or_insert_with(|| Hamiltonian::build_cycle(game));
+
+        Ok(match cycle {
+            Some(cycle) => {
+                let head = game.snake_body()[0];
+                cycle[head.0 as usize * game.dimensions.1 as usize + head.1 as usize]
+            }
+            None => Greedy::choose(game),
+        })
+    }
+}
+
+/// Direction from a field to one of its neighbours on the board.
+fn direction_between(from: (u16, u16), to: (u16, u16)) -> Direction {
+    if to.0 < from.0 {
+        Direction::Up
+    } else if to.0 > from.0 {
+        Direction::Down
+    } else if to.1 < from.1 {
+        Direction::Left
+    } else {
+        Direction::Right
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameOver;
+    use crate::level::Level;
+    use crate::settings::{GameSettings, WallMode};
+
+    fn new_game(dimensions: (u16, u16), walls: WallMode) -> Game {
+        Game::new(&GameSettings::for_test(Level::empty(dimensions), walls), 11)
+    }
+
+    /// Lets `controller` play until the game ends or `max_steps` moves were made.
+    fn autoplay(
+        game: &mut Game,
+        controller: &mut dyn Controller,
+        max_steps: u32,
+    ) -> Option<GameOver> {
+        for _ in 0..max_steps {
+            let direction = controller.next_direction(game).unwrap();
+            game.change_direction(direction);
+            if let Some(reason) = game.step().game_over {
+                return Some(reason);
+            }
+        }
+        None
+    }
+
+    #[test]
+    fn greedy_takes_the_shortcut_through_a_wrapping_edge() {
+        let mut game = new_game((8, 16), WallMode::Wrap);
+        game.food = (0, 14);
+
+        assert_eq!(Greedy.next_direction(&game), Ok(Direction::Left));
+    }
+
+    #[test]
+    fn greedy_does_not_leave_through_a_solid_edge() {
+        let mut game = new_game((8, 16), WallMode::Solid);
+        game.food = (0, 14);
+
+        assert_eq!(Greedy.next_direction(&game), Ok(Direction::Down));
+    }
+
+    #[test]
+    fn greedy_eats() {
+        let mut game = new_game((10, 10), WallMode::Wrap);
+        autoplay(&mut game, &mut Greedy, 500);
+
+        assert!(game.snake_length() > 10);
+    }
+
+    #[test]
+    fn hamiltonian_fills_boards_with_an_even_side() {
+        for dimensions in [(4, 6), (4, 5), (5, 4), (6, 3)] {
+            let mut game = new_game(dimensions, WallMode::Solid);
+            let result = autoplay(&mut game, &mut Hamiltonian::default(), 10_000);
+
+            assert_eq!(result, Some(GameOver::BoardFilled), "{:?}", dimensions);
+        }
+    }
+
+    #[test]
+    fn hamiltonian_plays_like_greedy_without_a_cycle() {
+        let mut game = new_game((5, 5), WallMode::Wrap);
+        game.food = (0, 3);
+
+        assert_eq!(
+            Hamiltonian::default().next_direction(&game),
+            Ok(Direction::Left)
+        );
+    }
+}
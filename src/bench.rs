@@ -0,0 +1,333 @@
+//! Headless games for comparing autopilot strategies.
+//!
+//! `snake-rs bench` plays one game per seed with a [`Strategy`], move after move and
+//! without a terminal, so a game takes as long as its moves take to compute. A game that
+//! goes on for twice the number of fields on the board without the snake eating counts as
+//! stalled, as the strategy is then most likely chasing its own tail forever.
+use clap::ValueEnum;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::autopilot::{Forfeit, Strategy};
+use crate::game::{Game, GameOver};
+use crate::scores::ScoreKey;
+use crate::settings::GameSettings;
+
+/// How the results are printed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    /// Aligned columns for reading
+    Table,
+    /// A single JSON object for scripts
+    Json,
+}
+
+/// Why a headless game ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Ending {
+    GameOver(GameOver),
+    /// The strategy gave up.
+    Forfeit(Forfeit),
+    /// The snake stopped eating, see the module documentation.
+    Stalled,
+}
+
+impl Ending {
+    /// Short name used as the key in the report.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Ending::GameOver(GameOver::SelfCollision) => "self_collision",
+            Ending::GameOver(GameOver::WallCollision) => "wall_collision",
+            Ending::GameOver(GameOver::BoardFilled) => "board_filled",
+            Ending::GameOver(GameOver::LevelCleared) => "level_cleared",
+            Ending::GameOver(GameOver::Won(_)) => "won",
+            Ending::GameOver(GameOver::Draw) => "draw",
+            Ending::Forfeit(_) => "forfeit",
+            Ending::Stalled => "stalled",
+        }
+    }
+}
+
+/// Outcome of a single headless game.
+#[derive(Debug)]
+pub struct GameResult {
+    pub seed: u64,
+    pub score: u32,
+    pub length: usize,
+    pub steps: u64,
+    pub ending: Ending,
+    /// Time spent choosing directions
+    pub controller_time: Duration,
+    /// Time spent moving the snake and spawning food
+    pub game_time: Duration,
+}
+
+/// Plays a game with `strategy` until it ends.
+pub fn play(settings: &GameSettings, strategy: Strategy, seed: u64) -> GameResult {
+    let mut game = Game::new(settings, seed);
+    let mut controller = strategy.controller();
+    let stall_limit = 2 * game.dimensions.0 as u64 * game.dimensions.1 as u64;
+    let mut moves_without_eating = 0;
+    let mut controller_time = Duration::ZERO;
+    let mut game_time = Duration::ZERO;
+
+    let ending = loop {
+        let start = Instant::now();
+        let direction = controller.next_direction(&game);
+        let decided = Instant::now();
+        controller_time += decided - start;
+        let direction = match direction {
+            Ok(direction) => direction,
+            Err(forfeit) => break Ending::Forfeit(forfeit),
+        };
+        game.change_direction(direction);
+        let outcome = game.step();
+        game_time += decided.elapsed();
+
+        if let Some(reason) = outcome.game_over {
+            break Ending::GameOver(reason);
+        }
+        if outcome.ate_food || outcome.ate_fruit {
+            moves_without_eating = 0;
+        } else {
+            moves_without_eating += 1;
+            if moves_without_eating > stall_limit {
+                break Ending::Stalled;
+            }
+        }
+    };
+
+    GameResult {
+        seed,
+        score: game.score,
+        length: game.snake_length(),
+        steps: game.steps,
+        ending,
+        controller_time,
+        game_time,
+    }
+}
+
+/// Plays one game for every seed, spread over `jobs` threads. The results are ordered by
+/// seed and do not depend on the number of threads.
+pub fn run(
+    settings: &GameSettings,
+    strategy: Strategy,
+    seeds: &[u64],
+    jobs: usize,
+) -> Vec<GameResult> {
+    let jobs = jobs.clamp(1, seeds.len().max(1));
+    let mut results: Vec<GameResult> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..jobs)
+            .map(|job| {
+                scope.spawn(move || {
+                    seeds
+                        .iter()
+                        .skip(job)
+                        .step_by(jobs)
+                        .map(|seed| play(settings, strategy, *seed))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap())
+            .collect()
+    });
+
+    results.sort_by_key(|result| result.seed);
+    results
+}
+
+/// Summary of one measured quantity over all games.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Stats {
+    pub mean: f64,
+    pub median: f64,
+    pub max: u64,
+}
+
+impl Stats {
+    pub fn new(values: impl IntoIterator<Item = u64>) -> Stats {
+        let mut values: Vec<u64> = values.into_iter().collect();
+        if values.is_empty() {
+            return Stats {
+                mean: 0.0,
+                median: 0.0,
+                max: 0,
+            };
+        }
+        values.sort_unstable();
+
+        let middle = values.len() / 2;
+        let median = if values.len().is_multiple_of(2) {
+            (values[middle - 1] + values[middle]) as f64 / 2.0
+        } else {
+            values[middle] as f64
+        };
+
+        Stats {
+            mean: values.iter().sum::<u64>() as f64 / values.len() as f64,
+            median,
+            max: values[values.len() - 1],
+        }
+    }
+}
+
+/// Aggregated results of a benchmark run.
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub strategy: String,
+    #[serde(flatten)]
+    pub settings: ScoreKey,
+    pub games: usize,
+    pub score: Stats,
+    pub length: Stats,
+    pub moves: Stats,
+    /// Number of games for every way they ended
+    pub endings: BTreeMap<&'static str, usize>,
+    /// Wall-clock time of the whole run
+    pub seconds: f64,
+    pub controller_micros_per_move: f64,
+    pub game_micros_per_move: f64,
+}
+
+impl Report {
+    pub fn new(
+        settings: &GameSettings,
+        strategy: Strategy,
+        results: &[GameResult],
+        elapsed: Duration,
+    ) -> Report {
+        let mut endings = BTreeMap::new();
+        for result in results {
+            *endings.entry(result.ending.name()).or_insert(0) += 1;
+        }
+
+        let total_moves = results
+            .iter()
+            .map(|result| result.steps)
+            .sum::<u64>()
+            .max(1);
+        let micros_per_move = |time: Duration| time.as_secs_f64() * 1e6 / total_moves as f64;
+
+        Report {
+            strategy: strategy.to_possible_value().unwrap().get_name().to_string(),
+            settings: ScoreKey::from_settings(settings),
+            games: results.len(),
+            score: Stats::new(results.iter().map(|result| result.score as u64)),
+            length: Stats::new(results.iter().map(|result| result.length as u64)),
+            moves: Stats::new(results.iter().map(|result| result.steps)),
+            endings,
+            seconds: elapsed.as_secs_f64(),
+            controller_micros_per_move: micros_per_move(
+                results.iter().map(|result| result.controller_time).sum(),
+            ),
+            game_micros_per_move: micros_per_move(
+                results.iter().map(|result| result.game_time).sum(),
+            ),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} games of {} on {}",
+            self.games, self.strategy, self.settings
+        )?;
+        writeln!(f)?;
+        writeln!(f, "{:<8} {:>10} {:>10} {:>10}", "", "mean", "median", "max")?;
+        for (name, stats) in [
+            ("score", &self.score),
+            ("length", &self.length),
+            ("moves", &self.moves),
+        ] {
+            writeln!(
+                f,
+                "{:<8} {:>10.1} {:>10.1} {:>10}",
+                name, stats.mean, stats.median, stats.max
+            )?;
+        }
+        writeln!(f)?;
+        writeln!(f, "Endings")?;
+        for (ending, games) in &self.endings {
+            writeln!(f, "  {:<16} {:>6}", ending, games)?;
+        }
+        writeln!(f)?;
+        write!(
+            f,
+            "Took {:.2}s, {:.2}µs per move in the controller and {:.2}µs in the game",
+            self.seconds, self.controller_micros_per_move, self.game_micros_per_move
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::Level;
+    use crate::settings::WallMode;
+
+    fn settings(dimensions: (u16, u16), walls: WallMode) -> GameSettings {
+        GameSettings::for_test(Level::empty(dimensions), walls)
+    }
+
+    #[test]
+    fn stats_of_odd_and_even_counts() {
+        assert_eq!(
+            Stats::new([3, 1, 8]),
+            Stats {
+                mean: 4.0,
+                median: 3.0,
+                max: 8
+            }
+        );
+        assert_eq!(Stats::new([4, 1, 2, 9]).median, 3.0);
+        assert_eq!(Stats::new([]).max, 0);
+    }
+
+    #[test]
+    fn results_do_not_depend_on_the_number_of_threads() {
+        let settings = settings((8, 12), WallMode::Wrap);
+        let seeds: Vec<u64> = (0..7).collect();
+        let summary = |results: Vec<GameResult>| -> Vec<(u64, u32, u64, Ending)> {
+            results
+                .iter()
+                .map(|r| (r.seed, r.score, r.steps, r.ending))
+                .collect()
+        };
+
+        let single = summary(run(&settings, Strategy::Greedy, &seeds, 1));
+        let parallel = summary(run(&settings, Strategy::Greedy, &seeds, 3));
+
+        assert_eq!(single, parallel);
+        assert_eq!(single.len(), 7);
+    }
+
+    #[test]
+    fn report_counts_endings() {
+        let settings = settings((4, 6), WallMode::Solid);
+        let results = run(&settings, Strategy::Hamiltonian, &[1, 2, 3], 2);
+        let report = Report::new(&settings, Strategy::Hamiltonian, &results, Duration::ZERO);
+
+        assert_eq!(report.endings, BTreeMap::from([("board_filled", 3)]));
+        assert_eq!(report.games, 3);
+
+        let json: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+        assert_eq!(json["strategy"], "hamiltonian");
+        assert_eq!(json["width"], 6);
+        assert_eq!(json["endings"]["board_filled"], 3);
+        assert_eq!(json["moves"]["max"], report.moves.max);
+    }
+}
@@ -1,19 +1,24 @@
-use crossterm::style::{StyledContent, Stylize};
+use crossterm::style::{Color, StyledContent, Stylize};
 
-use crate::{consts, game::Game};
+use crate::{
+    config::Theme,
+    game::{Game, Player},
+    settings::WallMode,
+};
 
 #[derive(Clone)]
 pub enum BoardPiece {
-    Snake,
-    SnakeHead,
+    Snake(Player),
+    SnakeHead(Player),
     Food,
     Fruit,
+    Wall,
     Empty,
 }
 
 type StyledBoard<'a> = Vec<Vec<StyledContent<&'a str>>>;
 
-pub fn style_game_board(game: &Game) -> StyledBoard {
+pub fn style_game_board<'a>(game: &Game, theme: &Theme) -> StyledBoard<'a> {
     let board_pieces = game.board_pieces();
 
     let is_fruit_blinking = if let Some((_, i)) = game.fruit {
@@ -26,37 +31,48 @@ pub fn style_game_board(game: &Game) -> StyledBoard {
         .map(|line| {
             line.iter()
                 .map(|field| match field {
-                    BoardPiece::Snake => "O"
-                        .with(consts::BOARD_FIELD_TEXT_COLOR)
-                        .on(consts::BOARD_FIELD_BACKGROUND_COLOR)
+                    BoardPiece::Snake(player) => "O"
+                        .with(snake_color(*player, theme))
+                        .on(theme.board)
                         .bold()
                         .dim(),
-                    BoardPiece::SnakeHead => "#"
-                        .with(consts::BOARD_FIELD_TEXT_COLOR)
-                        .on(consts::BOARD_FIELD_BACKGROUND_COLOR),
-                    BoardPiece::Food => "@"
-                        .with(consts::BOARD_FIELD_TEXT_COLOR)
-                        .on(consts::BOARD_FIELD_BACKGROUND_COLOR)
-                        .bold(),
+                    BoardPiece::SnakeHead(player) => {
+                        "#".with(snake_color(*player, theme)).on(theme.board)
+                    }
+                    BoardPiece::Food => "@".with(theme.board_text).on(theme.board).bold(),
                     BoardPiece::Fruit => if is_fruit_blinking { " " } else { "$" }
-                        .with(consts::BOARD_FIELD_TEXT_COLOR)
-                        .on(consts::BOARD_FIELD_BACKGROUND_COLOR)
+                        .with(theme.board_text)
+                        .on(theme.board)
                         .bold(),
-                    BoardPiece::Empty => " "
-                        .with(consts::BOARD_FIELD_TEXT_COLOR)
-                        .on(consts::BOARD_FIELD_BACKGROUND_COLOR),
+                    BoardPiece::Wall => " ".on(theme.border),
+                    BoardPiece::Empty => " ".with(theme.board_text).on(theme.board),
                 })
                 .collect::<Vec<_>>()
         })
         .collect();
-    decorate_with_walls(inner_board)
+    decorate_with_walls(inner_board, game.walls, theme)
+}
+
+fn snake_color(player: Player, theme: &Theme) -> Color {
+    match player {
+        Player::One => theme.board_text,
+        Player::Two => theme.second_snake,
+    }
 }
 
-fn decorate_with_walls(board: StyledBoard) -> StyledBoard {
+fn decorate_with_walls<'a>(
+    board: StyledBoard<'a>,
+    walls: WallMode,
+    theme: &Theme,
+) -> StyledBoard<'a> {
     let mut result: StyledBoard = vec![];
 
-    let wall_element = " ".on(consts::BOARD_BORDER_COLOR);
-    let wall_row = vec![vec![wall_element.clone(); board.first().unwrap().len() + 2]];
+    // Wrapping edges get a dotted border, so they don't look like solid walls
+    let wall_element = match walls {
+        WallMode::Solid => " ".on(theme.border),
+        WallMode::Wrap => "·".with(theme.wrap_border_text).on(theme.wrap_border),
+    };
+    let wall_row = vec![vec![wall_element; board.first().unwrap().len() + 2]];
 
     result.extend(wall_row.clone());
     result.extend(
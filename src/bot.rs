@@ -0,0 +1,303 @@
+//! Bots: external programs that steer the snake over their stdin and stdout.
+//!
+//! With `--bot-cmd "<program>"`, the program is started through the shell for every game
+//! and asked for a direction before every move. The game writes the state of the board
+//! as one JSON object per line to the program's stdin, shown here over several lines:
+//!
+//! ```text
+//! {"version":1,"step":0,"width":16,"height":8,"walls":"wrap","obstacles":[],
+//!  "snake":[[4,8],[4,7],[4,6]],"direction":"right","food":[2,11],
+//!  "fruit":{"position":[6,3],"moves_left":12},"score":0}
+//! ```
+//!
+//! Positions are `[row, col]` counted from the top left corner, and the snake's head comes
+//! first. `direction` is the snake's last move, `fruit` is `null` while there is none, and
+//! `version` goes up whenever a field changes its meaning.
+//!
+//! The program answers every line with a line of its own:
+//!
+//! ```text
+//! {"direction":"up"}
+//! ```
+//!
+//! where the direction is one of `up`, `down`, `left` or `right`. Turning back onto the
+//! snake's neck is ignored, like the same key press would be.
+//!
+//! The bot forfeits the game as soon as it takes longer than the timeout to answer, sends
+//! anything else, or exits. The first answer may take up to [`STARTUP_TIMEOUT`] instead,
+//! so the program has time to start. The program is stopped when the game ends, together
+//! with every program it started, as they all run in a process group of their own. Its
+//! stderr is discarded, as it would end up on the game screen; redirect it in the command
+//! to keep it.
+use serde::{Deserialize, Serialize};
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+use crate::autopilot::{Controller, Forfeit};
+use crate::direction::Direction;
+use crate::game::Game;
+use crate::settings::WallMode;
+
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Time the bot has for its first answer.
+pub const STARTUP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How to start a bot.
+#[derive(Clone, Debug)]
+pub struct BotCommand {
+    /// Shell command that starts the program
+    pub command: String,
+    /// Time the bot has for every answer after the first
+    pub timeout: Duration,
+}
+
+#[derive(Serialize)]
+struct State<'a> {
+    version: u32,
+    step: u64,
+    width: u16,
+    height: u16,
+    walls: WallMode,
+    obstacles: Vec<(u16, u16)>,
+    snake: &'a [(u16, u16)],
+    direction: Direction,
+    food: (u16, u16),
+    fruit: Option<Fruit>,
+    score: u32,
+}
+
+#[derive(Serialize)]
+struct Fruit {
+    position: (u16, u16),
+    moves_left: u16,
+}
+
+#[derive(Deserialize)]
+struct Reply {
+    direction: Direction,
+}
+
+/// The line sent to the bot before the next move.
+fn state_line(game: &Game) -> String {
+    let mut obstacles: Vec<(u16, u16)> = game.obstacles().iter().copied().collect();
+    obstacles.sort_unstable();
+
+    let state = State {
+        version: PROTOCOL_VERSION,
+        step: game.steps,
+        width: game.dimensions.1,
+        height: game.dimensions.0,
+        walls: game.walls,
+        obstacles,
+        snake: game.snake_body(),
+        direction: game.direction(),
+        food: game.food,
+        fruit: game.fruit.map(|(position, moves_left)| Fruit {
+            position,
+            moves_left,
+        }),
+        score: game.score,
+    };
+    serde_json::to_string(&state).unwrap()
+}
+
+fn shell(command: &str) -> Command {
+    let (shell, flag) = if cfg!(windows) {
+        ("cmd", "/C")
+    } else {
+        ("sh", "-c")
+    };
+    let mut shell = Command::new(shell);
+    shell.args([flag, command]);
+    shell
+}
+
+/// A running bot program.
+pub struct Bot {
+    child: Child,
+    stdin: ChildStdin,
+    /// Lines the program wrote to its stdout
+    replies: Receiver<String>,
+    timeout: Duration,
+    answered: bool,
+}
+
+impl Bot {
+    pub fn spawn(command: &BotCommand) -> io::Result<Bot> {
+        let mut shell = shell(&command.command);
+        #[cfg(unix)]
+        std::os::unix::process::CommandExt::process_group(&mut shell, 0);
+        let mut child = shell
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let stdin = child.stdin.take().unwrap();
+        let stdout = child.stdout.take().unwrap();
+
+        // Read in the background, so that waiting for an answer can time out
+        let (sender, replies) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Bot {
+            child,
+            stdin,
+            replies,
+            timeout: command.timeout,
+            answered: false,
+        })
+    }
+}
+
+impl Controller for Bot {
+    fn next_direction(&mut self, game: &Game) -> Result<Direction, Forfeit> {
+        writeln!(self.stdin, "{}", state_line(game))
+            .and_then(|()| self.stdin.flush())
+            .map_err(|_| Forfeit::Gone)?;
+
+        let timeout = if self.answered {
+            self.timeout
+        } else {
+            self.timeout.max(STARTUP_TIMEOUT)
+        };
+        let line = self.replies.recv_timeout(timeout).map_err(|e| match e {
+            RecvTimeoutError::Timeout => Forfeit::TooSlow,
+            RecvTimeoutError::Disconnected => Forfeit::Gone,
+        })?;
+        self.answered = true;
+
+        serde_json::from_str::<Reply>(&line)
+            .map(|reply| reply.direction)
+            .map_err(|_| Forfeit::InvalidMove)
+    }
+}
+
+impl Drop for Bot {
+    fn drop(&mut self) {
+        // The shell's process group also holds everything the command started
+        #[cfg(unix)]
+        // SAFETY: kill only sends a signal, the group is the one the child leads
+        unsafe {
+            libc::kill(-(self.child.id() as libc::pid_t), libc::SIGKILL);
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::level::Level;
+    use crate::settings::GameSettings;
+
+    fn game() -> Game {
+        let level = Level::parse("pillar", "......\n.#....\n..>...\n......\n").unwrap();
+        Game::new(&GameSettings::for_test(level, WallMode::Solid), 3)
+    }
+
+    /// Starts `script` with a timeout that only a bot that stops answering runs into.
+    fn bot(script: &str) -> Bot {
+        bot_with_timeout(script, Duration::from_secs(30))
+    }
+
+    fn bot_with_timeout(script: &str, timeout: Duration) -> Bot {
+        Bot::spawn(&BotCommand {
+            command: script.to_string(),
+            timeout,
+        })
+        .unwrap()
+    }
+
+    fn is_running(pid: &str) -> bool {
+        Command::new("kill")
+            .args(["-0", pid])
+            .stderr(Stdio::null())
+            .status()
+            .unwrap()
+            .success()
+    }
+
+    #[test]
+    fn state_describes_the_board() {
+        let mut game = game();
+        game.fruit = Some(((3, 5), 7));
+        let state: serde_json::Value = serde_json::from_str(&state_line(&game)).unwrap();
+
+        assert_eq!(state["version"], PROTOCOL_VERSION);
+        assert_eq!(state["width"], 6);
+        assert_eq!(state["height"], 4);
+        assert_eq!(state["walls"], "solid");
+        assert_eq!(state["obstacles"], serde_json::json!([[1, 1]]));
+        assert_eq!(state["snake"][0], serde_json::json!([2, 2]));
+        assert_eq!(state["direction"], "right");
+        assert_eq!(state["fruit"]["position"], serde_json::json!([3, 5]));
+        assert_eq!(state["fruit"]["moves_left"], 7);
+        assert_eq!(state["score"], 0);
+    }
+
+    #[test]
+    fn bot_answers_every_move() {
+        let mut bot = bot(r#"while read state; do echo '{"direction":"down"}'; done"#);
+        let mut game = game();
+
+        for _ in 0..2 {
+            let direction = bot.next_direction(&game).unwrap();
+            assert_eq!(direction, Direction::Down);
+            game.change_direction(direction);
+            game.step();
+        }
+    }
+
+    #[test]
+    fn slow_bot_forfeits() {
+        let mut bot = bot_with_timeout(
+            r#"read state; echo '{"direction":"up"}'; sleep 60"#,
+            Duration::from_millis(100),
+        );
+        let game = game();
+
+        assert_eq!(bot.next_direction(&game), Ok(Direction::Up));
+        assert_eq!(bot.next_direction(&game), Err(Forfeit::TooSlow));
+    }
+
+    #[test]
+    fn programs_started_by_the_bot_are_stopped_with_it() {
+        let bot = bot("sleep 60 & echo $!; wait");
+        let pid = bot.replies.recv_timeout(STARTUP_TIMEOUT).unwrap();
+        assert!(is_running(&pid));
+
+        drop(bot);
+        // The orphaned process is reaped by init once it is killed
+        let stopped = (0..100).any(|_| {
+            thread::sleep(Duration::from_millis(50));
+            !is_running(&pid)
+        });
+        assert!(stopped, "process {} is still running", pid);
+    }
+
+    #[test]
+    fn invalid_answer_forfeits() {
+        let mut bot = bot(r#"read state; echo '{"direction":"north"}'"#);
+
+        assert_eq!(bot.next_direction(&game()), Err(Forfeit::InvalidMove));
+    }
+
+    #[test]
+    fn exited_bot_forfeits() {
+        let mut bot = bot("exit 0");
+
+        assert_eq!(bot.next_direction(&game()), Err(Forfeit::Gone));
+    }
+}
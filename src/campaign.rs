@@ -0,0 +1,123 @@
+//! Built-in sequence of levels, each with its own board, speed and goal.
+//!
+//! The number of the highest level the player has unlocked is kept in
+//! `campaign-progress` in the data directory, as a single decimal number.
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use crate::level::Level;
+use crate::settings::{GameSettings, Goal, WallMode};
+use crate::storage;
+
+pub struct CampaignLevel {
+    pub name: &'static str,
+    map: &'static str,
+    walls: WallMode,
+    difficulty: u16,
+    goal: Goal,
+}
+
+pub const LEVELS: [CampaignLevel; 5] = [
+    CampaignLevel {
+        name: "Warm-up",
+        map: include_str!("../levels/campaign/01-warm-up.txt"),
+        walls: WallMode::Wrap,
+        difficulty: 3,
+        goal: Goal::Length(10),
+    },
+    CampaignLevel {
+        name: "Pillars",
+        map: include_str!("../levels/campaign/02-pillars.txt"),
+        walls: WallMode::Solid,
+        difficulty: 4,
+        goal: Goal::Length(15),
+    },
+    CampaignLevel {
+        name: "Corridors",
+        map: include_str!("../levels/campaign/03-corridors.txt"),
+        walls: WallMode::Solid,
+        difficulty: 5,
+        goal: Goal::Score(80),
+    },
+    CampaignLevel {
+        name: "Cross",
+        map: include_str!("../levels/campaign/04-cross.txt"),
+        walls: WallMode::Wrap,
+        difficulty: 6,
+        goal: Goal::Length(25),
+    },
+    CampaignLevel {
+        name: "Maze",
+        map: include_str!("../levels/campaign/05-maze.txt"),
+        walls: WallMode::Solid,
+        difficulty: 7,
+        goal: Goal::Score(200),
+    },
+];
+
+pub struct Campaign {
+    /// Index into [`LEVELS`]
+    pub level: usize,
+}
+
+impl Campaign {
+    pub fn new(level: usize) -> Campaign {
+        Campaign {
+            level: level.min(LEVELS.len() - 1),
+        }
+    }
+
+    pub fn current(&self) -> &'static CampaignLevel {
+        &LEVELS[self.level]
+    }
+
+    pub fn settings(&self) -> GameSettings {
+        let current = self.current();
+        GameSettings {
+            level: Level::parse(&format!("campaign-{}", self.level + 1), current.map)
+                .expect("built-in level must be valid"),
+            difficulty: current.difficulty,
+            walls: current.walls,
+            goal: Some(current.goal),
+            versus: false,
+        }
+    }
+
+    /// Moves on to the next level and unlocks it. Returns `false` if the campaign is finished.
+    pub fn advance(&mut self) -> bool {
+        if self.level + 1 >= LEVELS.len() {
+            return false;
+        }
+
+        self.level += 1;
+        if self.level + 1 > load_unlocked() {
+            // Losing progress is not worth interrupting the game for
+            let _ = save_unlocked(self.level + 1);
+        }
+        true
+    }
+}
+
+fn progress_file() -> Option<PathBuf> {
+    storage::data_dir().map(|dir| dir.join("campaign-progress"))
+}
+
+/// Number of the highest unlocked level, starting from 1.
+pub fn load_unlocked() -> usize {
+    progress_file()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|text| text.trim().parse().ok())
+        .unwrap_or(1)
+        .clamp(1, LEVELS.len())
+}
+
+fn save_unlocked(level: usize) -> io::Result<()> {
+    let Some(path) = progress_file() else {
+        return Ok(());
+    };
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, format!("{}\n", level))
+}
@@ -1,3 +1,302 @@
+//! Runtime configuration and the optional TOML config file.
+//!
+//! The config file is read from `$XDG_CONFIG_HOME/snake-rs/config.toml` (usually
+//! `~/.config/snake-rs/config.toml`) or from the path given with `--config`. Every key is
+//! optional, and options passed on the command line take precedence:
+//!
+//! ```toml
+//! difficulty = 5
+//! width = 16
+//! height = 8
+//! walls = "wrap"       # or "solid"
+//! show-fps = false
+//! input-buffer = 3     # turns that can be queued ahead of the snake, 1-8
+//!
+//! [colors]             # ANSI color numbers (0-255), names such as "dark_red", or "#rrggbb"
+//! board = 52
+//! board-text = "white"
+//! border = 232
+//! wrap-border = 235
+//! wrap-border-text = 245
+//! second-snake = 220   # the second player's snake in versus games
+//!
+//! [keys]               # replaces the default keys of each listed action
+//! up = ["up", "k"]
+//! down = ["down", "j"]
+//! left = ["left", "h"]
+//! right = ["right", "l"]
+//! player2-up = ["w"]   # the second player in versus games, otherwise the same as up
+//! player2-down = ["s"]
+//! player2-left = ["a"]
+//! player2-right = ["d"]
+//! pause = ["esc"]
+//! quit = ["q", "x"]
+//! restart = ["r"]
+//! menu = ["m"]
+//! confirm = ["enter"]
+//! ```
+use crossterm::style::Color;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::autopilot::Autopilot;
+use crate::consts;
+use crate::keymap::{Action, Keymap};
+use crate::settings::WallMode;
+use crate::storage;
+
 pub struct Config {
     pub show_fps_counter: bool,
+    /// How many turns can be queued ahead of the snake's moves
+    pub input_buffer: usize,
+    pub theme: Theme,
+    pub keymap: Keymap,
+    /// Lets the computer steer the snake
+    pub autopilot: Option<Autopilot>,
+}
+
+/// Colors of the game board.
+pub struct Theme {
+    pub board: Color,
+    pub board_text: Color,
+    pub border: Color,
+    pub wrap_border: Color,
+    pub wrap_border_text: Color,
+    /// The second player's snake
+    pub second_snake: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Theme {
+        Theme {
+            board: consts::BOARD_FIELD_BACKGROUND_COLOR,
+            board_text: consts::BOARD_FIELD_TEXT_COLOR,
+            border: consts::BOARD_BORDER_COLOR,
+            wrap_border: consts::BOARD_WRAP_BORDER_COLOR,
+            wrap_border_text: consts::BOARD_WRAP_BORDER_TEXT_COLOR,
+            second_snake: consts::BOARD_SECOND_SNAKE_COLOR,
+        }
+    }
+}
+
+/// Contents of the config file, `None` for every key that is not set.
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ConfigFile {
+    pub difficulty: Option<u16>,
+    pub width: Option<u16>,
+    pub height: Option<u16>,
+    pub walls: Option<WallMode>,
+    pub show_fps: Option<bool>,
+    pub input_buffer: Option<u8>,
+    pub colors: ColorsFile,
+    /// Keys for every action, see [`crate::keymap`] for key names
+    pub keys: BTreeMap<Action, Vec<String>>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct ColorsFile {
+    board: Option<ColorValue>,
+    board_text: Option<ColorValue>,
+    border: Option<ColorValue>,
+    wrap_border: Option<ColorValue>,
+    wrap_border_text: Option<ColorValue>,
+    second_snake: Option<ColorValue>,
+}
+
+impl ColorsFile {
+    pub fn theme(&self) -> Theme {
+        let default = Theme::default();
+        let pick = |value: &Option<ColorValue>, default: Color| value.map_or(default, |v| v.0);
+
+        Theme {
+            board: pick(&self.board, default.board),
+            board_text: pick(&self.board_text, default.board_text),
+            border: pick(&self.border, default.border),
+            wrap_border: pick(&self.wrap_border, default.wrap_border),
+            wrap_border_text: pick(&self.wrap_border_text, default.wrap_border_text),
+            second_snake: pick(&self.second_snake, default.second_snake),
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(try_from = "RawColor")]
+struct ColorValue(Color);
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawColor {
+    Ansi(u8),
+    Text(String),
+}
+
+impl TryFrom<RawColor> for ColorValue {
+    type Error = String;
+
+    fn try_from(raw: RawColor) -> Result<ColorValue, String> {
+        let text = match raw {
+            RawColor::Ansi(value) => return Ok(ColorValue(Color::AnsiValue(value))),
+            RawColor::Text(text) => text,
+        };
+
+        if let Some(hex) = text.strip_prefix('#') {
+            let channel = |i: usize| {
+                hex.get(i..i + 2)
+                    .and_then(|c| u8::from_str_radix(c, 16).ok())
+            };
+            if let (6, Some(r), Some(g), Some(b)) = (hex.len(), channel(0), channel(2), channel(4))
+            {
+                return Ok(ColorValue(Color::Rgb { r, g, b }));
+            }
+        }
+
+        Color::try_from(text.as_str())
+            .map(ColorValue)
+            .map_err(|_| format!("unknown color '{}'", text))
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(PathBuf, io::Error),
+    Parse(PathBuf, String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(path, e) => write!(f, "{}: {}", path.display(), e),
+            ConfigError::Parse(path, message) => write!(f, "{}: {}", path.display(), message),
+        }
+    }
+}
+
+impl ConfigFile {
+    /// Reads the config file from `path`, or from the default location if `path` is `None`.
+    /// A missing file at the default location is not an error.
+    pub fn load(path: Option<&Path>) -> Result<ConfigFile, ConfigError> {
+        let (path, required) = match path {
+            Some(path) => (path.to_path_buf(), true),
+            None => match storage::config_dir() {
+                Some(dir) => (dir.join("config.toml"), false),
+                None => return Ok(ConfigFile::default()),
+            },
+        };
+
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound && !required => {
+                return Ok(ConfigFile::default())
+            }
+            Err(e) => return Err(ConfigError::Io(path, e)),
+        };
+
+        let file: ConfigFile =
+            toml::from_str(&text).map_err(|e| ConfigError::Parse(path.clone(), e.to_string()))?;
+        file.validate()
+            .map_err(|message| ConfigError::Parse(path, message))?;
+
+        Ok(file)
+    }
+
+    /// Applies the same limits as the command line options and checks the key bindings.
+    fn validate(&self) -> Result<(), String> {
+        let check = |name: &str, value: Option<u16>, min: u16, max: u16| match value {
+            Some(value) if value < min || value > max => Err(format!(
+                "{} must be between {} and {}, got {}",
+                name, min, max, value
+            )),
+            _ => Ok(()),
+        };
+
+        check("difficulty", self.difficulty, 1, 9)?;
+        check("width", self.width, 3, 255)?;
+        check("height", self.height, 3, 255)?;
+        check("input-buffer", self.input_buffer.map(u16::from), 1, 8)?;
+
+        Keymap::with_overrides(&self.keys).map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Result<ConfigFile, String> {
+        let file: ConfigFile = toml::from_str(text).map_err(|e| e.to_string())?;
+        file.validate()?;
+        Ok(file)
+    }
+
+    fn theme(text: &str) -> Theme {
+        parse(text).unwrap().colors.theme()
+    }
+
+    #[test]
+    fn empty_file_keeps_the_defaults() {
+        let file = parse("").unwrap();
+
+        assert_eq!(file.difficulty, None);
+        assert_eq!(file.colors.theme().board, Theme::default().board);
+    }
+
+    #[test]
+    fn values_outside_their_range_are_rejected() {
+        assert_eq!(
+            parse("difficulty = 0").err().unwrap(),
+            "difficulty must be between 1 and 9, got 0"
+        );
+        assert_eq!(
+            parse("width = 2").err().unwrap(),
+            "width must be between 3 and 255, got 2"
+        );
+        assert_eq!(
+            parse("input-buffer = 9").err().unwrap(),
+            "input-buffer must be between 1 and 8, got 9"
+        );
+        assert!(parse("difficulty = 9\nwidth = 255\nheight = 3\ninput-buffer = 1").is_ok());
+    }
+
+    #[test]
+    fn invalid_values_are_rejected() {
+        assert!(parse("walls = \"bouncy\"").is_err());
+        assert!(parse("height = -1").is_err());
+        assert!(parse("speed = 3").is_err());
+        assert!(parse("[keys]\njump = [\"space\"]").is_err());
+    }
+
+    #[test]
+    fn colors_are_read_as_numbers_names_or_hex() {
+        let theme = theme("[colors]\nboard = 52\nboard-text = \"dark_red\"\nborder = \"#0a80FF\"");
+
+        assert_eq!(theme.board, Color::AnsiValue(52));
+        assert_eq!(theme.board_text, Color::DarkRed);
+        assert_eq!(
+            theme.border,
+            Color::Rgb {
+                r: 10,
+                g: 128,
+                b: 255
+            }
+        );
+        assert_eq!(theme.wrap_border, Theme::default().wrap_border);
+    }
+
+    #[test]
+    fn unknown_colors_are_rejected() {
+        for color in ["\"purplish\"", "\"#12345\"", "\"#12345g\"", "256"] {
+            let error = parse(&format!("[colors]\nboard = {}", color));
+            assert!(error.is_err(), "{} was accepted", color);
+        }
+        assert!(parse("[colors]\nboard = \"purplish\"")
+            .err()
+            .unwrap()
+            .contains("unknown color 'purplish'"));
+    }
 }
@@ -1,3 +1,11 @@
+pub mod frame;
 pub mod game;
+pub mod game_over;
+pub mod main_menu;
+pub mod network;
 pub mod pause_menu;
+pub mod renderer;
+pub mod replay;
+#[cfg(test)]
+mod snapshots;
 pub mod utils;
@@ -0,0 +1,184 @@
+use crossterm::{
+    cursor::MoveTo,
+    queue,
+    style::{ContentStyle, PrintStyledContent, StyledContent},
+};
+use std::io::Write;
+
+use crate::window::WindowDimensions;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Cell {
+    pub symbol: char,
+    pub style: ContentStyle,
+}
+
+impl Cell {
+    pub fn new(symbol: char, style: ContentStyle) -> Cell {
+        Cell { symbol, style }
+    }
+}
+
+/// Contents of the whole window, one cell per character.
+#[derive(Clone)]
+pub struct Frame {
+    pub dimensions: WindowDimensions,
+    cells: Vec<Cell>,
+}
+
+impl Frame {
+    pub fn new(dimensions: WindowDimensions, fill: Cell) -> Frame {
+        Frame {
+            dimensions,
+            cells: vec![fill; dimensions.0 as usize * dimensions.1 as usize],
+        }
+    }
+
+    pub fn get(&self, row: u16, col: u16) -> Cell {
+        self.cells[self.index(row, col)]
+    }
+
+    /// Sets a single cell, anything outside the window is cut off.
+    pub fn set(&mut self, row: u16, col: u16, cell: Cell) {
+        if row < self.dimensions.0 && col < self.dimensions.1 {
+            let index = self.index(row, col);
+            self.cells[index] = cell;
+        }
+    }
+
+    /// Writes `text` starting at the given position, cutting it off at the window edge.
+    pub fn print(&mut self, row: u16, col: u16, text: &str, style: ContentStyle) {
+        for (i, symbol) in text.chars().enumerate() {
+            self.set(row, col.saturating_add(i as u16), Cell::new(symbol, style));
+        }
+    }
+
+    /// The symbols of every row, without any styling.
+    #[cfg(test)]
+    pub fn lines(&self) -> Vec<String> {
+        self.cells
+            .chunks(self.dimensions.1.max(1) as usize)
+            .map(|row| row.iter().map(|cell| cell.symbol).collect())
+            .collect()
+    }
+
+    fn index(&self, row: u16, col: u16) -> usize {
+        row as usize * self.dimensions.1 as usize + col as usize
+    }
+}
+
+/// Remembers the frame that is currently shown in the terminal, so that drawing the next
+/// one only sends the cells that changed.
+#[derive(Default)]
+pub struct FrameBuffer {
+    last: Option<Frame>,
+}
+
+impl FrameBuffer {
+    /// Forgets the previous frame, so the next one is drawn in full.
+    pub fn invalidate(&mut self) {
+        self.last = None;
+    }
+
+    /// Draws `frame`, sending only the cells that differ from the previous one. Adjacent
+    /// changed cells with the same style are printed together.
+    pub fn present(&mut self, frame: Frame, out: &mut impl Write) -> std::io::Result<()> {
+        let last = self
+            .last
+            .take()
+            .filter(|last| last.dimensions == frame.dimensions);
+
+        for row in 0..frame.dimensions.0 {
+            let mut col = 0;
+            while col < frame.dimensions.1 {
+                let cell = frame.get(row, col);
+                if last.as_ref().is_some_and(|last| last.get(row, col) == cell) {
+                    col += 1;
+                    continue;
+                }
+
+                let start = col;
+                let mut text = String::new();
+                while col < frame.dimensions.1 {
+                    let next = frame.get(row, col);
+                    let unchanged = last.as_ref().is_some_and(|last| last.get(row, col) == next);
+                    if unchanged || next.style != cell.style {
+                        break;
+                    }
+                    text.push(next.symbol);
+                    col += 1;
+                }
+
+                queue!(
+                    out,
+                    MoveTo(start, row),
+                    PrintStyledContent(StyledContent::new(cell.style, text))
+                )?;
+            }
+        }
+
+        self.last = Some(frame);
+        out.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::style::{Color, Stylize};
+
+    fn plain() -> ContentStyle {
+        ContentStyle::new().with(Color::White).on(Color::Black)
+    }
+
+    fn present(screen: &mut FrameBuffer, frame: Frame) -> String {
+        let mut out = vec![];
+        screen.present(frame, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn first_frame_is_drawn_in_full() {
+        let mut screen = FrameBuffer::default();
+        let output = present(&mut screen, Frame::new((2, 3), Cell::new('.', plain())));
+
+        assert_eq!(output.matches("...").count(), 2);
+    }
+
+    #[test]
+    fn unchanged_frame_emits_nothing() {
+        let mut screen = FrameBuffer::default();
+        present(&mut screen, Frame::new((2, 3), Cell::new('.', plain())));
+
+        let output = present(&mut screen, Frame::new((2, 3), Cell::new('.', plain())));
+        assert!(output.is_empty());
+    }
+
+    #[test]
+    fn adjacent_changes_with_the_same_style_are_merged() {
+        let mut screen = FrameBuffer::default();
+        present(&mut screen, Frame::new((3, 10), Cell::new(' ', plain())));
+
+        let mut frame = Frame::new((3, 10), Cell::new(' ', plain()));
+        frame.print(1, 2, "abc", plain());
+        frame.print(1, 5, "de", plain().bold());
+        frame.set(2, 9, Cell::new('f', plain()));
+        let output = present(&mut screen, frame);
+
+        // One move per run: "abc", the bold "de" and "f"
+        assert_eq!(output.matches('H').count(), 3);
+        assert!(output.contains("\x1b[2;3H"));
+        assert!(output.contains("abc"));
+        assert!(output.contains("\x1b[2;6H"));
+        assert!(output.contains("\x1b[3;10H"));
+    }
+
+    #[test]
+    fn resized_frame_is_drawn_in_full() {
+        let mut screen = FrameBuffer::default();
+        present(&mut screen, Frame::new((2, 3), Cell::new('.', plain())));
+
+        let output = present(&mut screen, Frame::new((2, 4), Cell::new('.', plain())));
+        assert_eq!(output.matches("....").count(), 2);
+    }
+}
@@ -1,122 +1,97 @@
-use crossterm::{
-    cursor::MoveTo,
-    queue,
-    style::{Print, PrintStyledContent, SetBackgroundColor, SetForegroundColor},
-    terminal::{Clear, ClearType},
-};
-use std::{
-    io::{Stdout, Write},
-    time::Duration,
-};
+use crossterm::style::{ContentStyle, Stylize};
+use std::time::Duration;
 
 use crate::{
-    board::style_game_board, config::Config, consts, game::Game, window::WindowDimensions,
+    board::style_game_board,
+    config::Config,
+    consts,
+    draw::frame::Frame,
+    draw::utils::{background_frame, background_style},
+    game::Game,
+    window::WindowDimensions,
 };
 
 pub fn is_window_big_enough(game: &Game, window_dim: WindowDimensions) -> bool {
     window_dim.0 >= game.dimensions.0 + 4 && window_dim.1 >= game.dimensions.1 + 4
 }
 
-pub fn draw_game_frame(
+/// Composes everything on the game screen into one frame.
+pub fn game_frame(
     game: &Game,
     config: &Config,
     window_dim: WindowDimensions,
     last_delta: Duration,
-    stdout: &mut Stdout,
-) -> std::io::Result<()> {
-    queue_draw_board(game, window_dim, stdout)?;
-    queue_draw_score(game, window_dim, stdout)?;
-    queue_draw_fruit_timer(game, window_dim, stdout)?;
+) -> Frame {
+    let mut frame = background_frame(window_dim);
+
+    draw_board(game, config, &mut frame);
+    draw_score(game, &mut frame);
+    draw_fruit_timer(game, &mut frame);
     if config.show_fps_counter {
-        queue_draw_fps(last_delta, stdout)?;
+        draw_fps(last_delta, &mut frame);
     }
 
-    stdout.flush()?;
-
-    Ok(())
+    frame
 }
 
-fn queue_draw_board(
-    game: &Game,
-    window_dim: WindowDimensions,
-    stdout: &mut Stdout,
-) -> std::io::Result<()> {
-    let game_screen_start =
-        game_screen_starting_position((window_dim.0, window_dim.1), game.dimensions);
+fn draw_board(game: &Game, config: &Config, frame: &mut Frame) {
+    let game_screen_start = game_screen_starting_position(frame.dimensions, game.dimensions);
 
-    let board = style_game_board(game);
+    let board = style_game_board(game, &config.theme);
 
     board.iter().enumerate().for_each(|(row, line)| {
         line.iter().enumerate().for_each(|(col, element)| {
-            queue!(
-                stdout,
-                MoveTo(
-                    game_screen_start.1 + col as u16,
-                    game_screen_start.0 + row as u16
-                ),
-                PrintStyledContent(*element)
+            frame.print(
+                game_screen_start.0 + row as u16,
+                game_screen_start.1 + col as u16,
+                element.content(),
+                *element.style(),
             )
-            .unwrap()
         });
     });
-
-    Ok(())
 }
 
-fn queue_draw_score(
-    game: &Game,
-    window_dim: WindowDimensions,
-    stdout: &mut Stdout,
-) -> std::io::Result<()> {
+fn draw_score(game: &Game, frame: &mut Frame) {
     let (starting_row, starting_col) =
-        game_screen_starting_position((window_dim.0, window_dim.1), game.dimensions);
-
-    queue!(
-        stdout,
-        SetBackgroundColor(consts::BACKGROUND_COLOR),
-        SetForegroundColor(consts::BACKGROUND_TEXT_COLOR)
-    )?;
-
-    let score_line = format!("Score: {}", game.score);
-    queue!(
-        stdout,
-        MoveTo(starting_col, starting_row + (game.dimensions.0 as u16) + 2),
-        Print(score_line)
-    )?;
-
-    Ok(())
+        game_screen_starting_position(frame.dimensions, game.dimensions);
+
+    let score_line = match (game.rival(), game.goal) {
+        (Some(rival), _) => format!("Player 1: {}  Player 2: {}", game.score, rival.score),
+        (None, Some(goal)) => format!(
+            "Score: {}  Length: {}  Goal: {}",
+            game.score,
+            game.snake_length(),
+            goal
+        ),
+        (None, None) => format!("Score: {}", game.score),
+    };
+    // Lines wider than the board move left, so they are not cut off by the window edge
+    let line_width = score_line.chars().count() as u16;
+    let starting_col = starting_col.min(frame.dimensions.1.saturating_sub(line_width));
+    frame.print(
+        starting_row + game.dimensions.0 + 2,
+        starting_col,
+        &score_line,
+        background_style(),
+    );
 }
 
-fn queue_draw_fruit_timer(
-    game: &Game,
-    window_dim: WindowDimensions,
-    stdout: &mut Stdout,
-) -> std::io::Result<()> {
+fn draw_fruit_timer(game: &Game, frame: &mut Frame) {
     let (starting_row, starting_col) =
-        game_screen_starting_position((window_dim.0, window_dim.1), game.dimensions);
-
-    // If fruit is not available, clear the line (this could be improved, we need to do it max once per fruit cycle)
-    queue!(
-        stdout,
-        MoveTo(starting_col, starting_row - 1),
-        Clear(ClearType::UntilNewLine),
-    )?;
-
-    if let Some(_) = game.fruit {
-        let text_line = format!("$ {}", game.fruit.unwrap().1);
-
-        queue!(
-            stdout,
-            SetBackgroundColor(consts::BACKGROUND_COLOR),
-            SetForegroundColor(consts::BACKGROUND_TEXT_COLOR),
-            Print(text_line)
-        )?;
+        game_screen_starting_position(frame.dimensions, game.dimensions);
+
+    if let Some((_, remaining_moves)) = game.fruit {
+        let text_line = format!("$ {}", remaining_moves);
+        frame.print(
+            starting_row - 1,
+            starting_col,
+            &text_line,
+            background_style(),
+        );
     }
-
-    Ok(())
 }
 
-fn queue_draw_fps(last_delta: Duration, stdout: &mut Stdout) -> std::io::Result<()> {
+fn draw_fps(last_delta: Duration, frame: &mut Frame) {
     let delta = if last_delta.as_secs_f64() == 0. {
         1.0
     } else {
@@ -124,16 +99,10 @@ fn queue_draw_fps(last_delta: Duration, stdout: &mut Stdout) -> std::io::Result<
     };
     let fps = (1.0 / delta) as u16;
 
-    queue!(
-        stdout,
-        MoveTo(0, 0),
-        SetBackgroundColor(consts::FPS_COUNTER_BACKGROUND_COLOR),
-        SetForegroundColor(consts::FPS_COUNTER_TEXT_COLOR),
-        Print(fps as u16),
-        SetBackgroundColor(consts::BACKGROUND_COLOR),
-    )?;
-
-    Ok(())
+    let style = ContentStyle::new()
+        .with(consts::FPS_COUNTER_TEXT_COLOR)
+        .on(consts::FPS_COUNTER_BACKGROUND_COLOR);
+    frame.print(0, 0, &fps.to_string(), style);
 }
 
 fn game_screen_starting_position(
@@ -141,7 +110,7 @@ fn game_screen_starting_position(
     board_dimensions: (u16, u16),
 ) -> (u16, u16) {
     (
-        (window_dim.0 - (board_dimensions.0 + 2) as u16) / 2,
-        (window_dim.1 - (board_dimensions.1 + 2) as u16) / 2,
+        (window_dim.0 - (board_dimensions.0 + 2)) / 2,
+        (window_dim.1 - (board_dimensions.1 + 2)) / 2,
     )
 }
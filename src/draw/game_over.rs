@@ -0,0 +1,85 @@
+use crate::{
+    draw::frame::Frame,
+    draw::utils::{background_frame, draw_centered_box},
+    game::GameOver,
+    game_loop::{GameEnd, GameSummary},
+    keymap::{Action, Keymap},
+    window::WindowDimensions,
+};
+
+/// Draws the final results. While `name_input` is set, the player is typing their name
+/// for the high score table instead of choosing what to do next.
+pub fn game_over_screen_frame(
+    summary: &GameSummary,
+    name_input: Option<&str>,
+    keymap: &Keymap,
+    window_dim: WindowDimensions,
+) -> Frame {
+    let mut frame = background_frame(window_dim);
+    draw_centered_box(
+        &mut frame,
+        &screen_lines(summary, name_input, keymap),
+        "GAME OVER",
+    );
+    frame
+}
+
+fn screen_lines(summary: &GameSummary, name_input: Option<&str>, keymap: &Keymap) -> Vec<String> {
+    let title = match summary.reason {
+        GameEnd::GameOver(GameOver::Won(player)) => {
+            format!("{} WINS", player.to_string().to_uppercase())
+        }
+        GameEnd::GameOver(GameOver::Draw) => String::from("DRAW"),
+        GameEnd::GameOver(reason) if reason.is_victory() => String::from("YOU WIN"),
+        _ => String::from("GAME OVER"),
+    };
+    let time_played = summary.time_played.as_secs();
+    let best = match summary.best {
+        Some(best) => best.to_string(),
+        None => String::from("-"),
+    };
+
+    let mut lines = vec![
+        title,
+        String::new(),
+        format!("{}.", summary.reason),
+        String::new(),
+    ];
+    match summary.rival_score {
+        Some(rival_score) => lines.extend([
+            format!("Player 1: {}", summary.score),
+            format!("Player 2: {}", rival_score),
+            format!("Time:     {}:{:02}", time_played / 60, time_played % 60),
+        ]),
+        None => lines.extend([
+            format!("Score:  {}", summary.score),
+            format!("Length: {}", summary.length),
+            format!("Time:   {}:{:02}", time_played / 60, time_played % 60),
+            format!("Best:   {}", best),
+        ]),
+    }
+    lines.push(String::new());
+
+    match name_input {
+        Some(name) => lines.extend([
+            String::from("New high score! Enter your name:"),
+            format!("> {:<16}", format!("{}_", name)),
+            String::new(),
+            String::from("<enter> - Save"),
+        ]),
+        None => {
+            let restart = format!("<{}>", keymap.key_name(Action::Restart));
+            let menu = format!("<{}>", keymap.key_name(Action::Menu));
+            let quit = format!("<{}>", keymap.key_name(Action::Quit));
+            let width = restart.len().max(menu.len()).max(quit.len());
+
+            lines.extend([
+                format!("{:<width$} - Restart game", restart),
+                format!("{:<width$} - Main menu", menu),
+                format!("{:<width$} - Quit game", quit),
+            ])
+        }
+    }
+
+    lines
+}
@@ -0,0 +1,64 @@
+use crate::{
+    draw::frame::Frame,
+    draw::utils::{background_frame, draw_centered_box},
+    keymap::{Action, Keymap},
+    menu::{MainMenu, MenuItem},
+    settings::WallMode,
+    window::WindowDimensions,
+};
+
+pub fn main_menu_frame(menu: &MainMenu, keymap: &Keymap, window_dim: WindowDimensions) -> Frame {
+    let mut frame = background_frame(window_dim);
+    draw_centered_box(&mut frame, &menu_lines(menu, keymap), "SNAKE");
+    frame
+}
+
+fn menu_lines(menu: &MainMenu, keymap: &Keymap) -> Vec<String> {
+    let mut lines = vec![String::from("S N A K E"), String::new()];
+
+    lines.extend(menu.items().map(|(item, selected)| {
+        let marker = if selected { ">" } else { " " };
+        let label = match item {
+            MenuItem::Play => String::from("Play"),
+            MenuItem::Campaign => String::from("Campaign"),
+            MenuItem::Difficulty => format!("Difficulty  < {:>5} >", menu.difficulty),
+            MenuItem::Width => format!("Width       < {:>5} >", menu.width),
+            MenuItem::Height => format!("Height      < {:>5} >", menu.height),
+            MenuItem::Walls => {
+                let walls = match menu.walls {
+                    WallMode::Solid => "solid",
+                    WallMode::Wrap => "wrap",
+                };
+                format!("Walls       < {:>5} >", walls)
+            }
+            MenuItem::Players => {
+                let players = if menu.versus { 2 } else { 1 };
+                format!("Players     < {:>5} >", players)
+            }
+            MenuItem::Quit => String::from("Quit"),
+        };
+        format!("{} {:<22}", marker, label)
+    }));
+
+    let select = format!(
+        "<{}/{}>",
+        keymap.key_name(Action::Up),
+        keymap.key_name(Action::Down)
+    );
+    let change = format!(
+        "<{}/{}>",
+        keymap.key_name(Action::Left),
+        keymap.key_name(Action::Right)
+    );
+    let confirm = format!("<{}>", keymap.key_name(Action::Confirm));
+    let width = select.len().max(change.len()).max(confirm.len());
+
+    lines.extend([
+        String::new(),
+        format!("{:<width$} - Select", select),
+        format!("{:<width$} - Change", change),
+        format!("{:<width$} - Confirm", confirm),
+    ]);
+
+    lines
+}
@@ -0,0 +1,96 @@
+use std::time::Duration;
+
+use crate::{
+    config::Config,
+    draw::frame::Frame,
+    draw::game::{game_frame, is_window_big_enough},
+    draw::utils::{background_frame, background_style, draw_centered_box},
+    keymap::{Action, Keymap},
+    net::client::{Session, SessionState},
+    window::WindowDimensions,
+};
+
+/// The screen of an online game: the lobby until the first game starts, then the board
+/// with the state of the game on the bottom line.
+pub fn online_frame(
+    session: &Session,
+    config: &Config,
+    window_dim: WindowDimensions,
+    last_delta: Duration,
+) -> Frame {
+    let Some(game) = &session.game else {
+        return lobby_frame(session, &config.keymap, window_dim);
+    };
+
+    let mut frame = if is_window_big_enough(game, window_dim) {
+        game_frame(game, config, window_dim, last_delta)
+    } else {
+        let mut frame = background_frame(window_dim);
+        frame.print(0, 0, "Window too small", background_style());
+        frame
+    };
+
+    frame.print(
+        window_dim.0.saturating_sub(1),
+        0,
+        &status_line(session, &config.keymap),
+        background_style(),
+    );
+    frame
+}
+
+fn lobby_frame(session: &Session, keymap: &Keymap, window_dim: WindowDimensions) -> Frame {
+    let mut frame = background_frame(window_dim);
+
+    let mut lines = vec![String::from("ONLINE GAME"), String::new()];
+    for seat in 1..=2 {
+        lines.push(format!("Player {}    {}", seat, player_name(session, seat)));
+    }
+    lines.push(format!("Spectators  {}", session.spectators));
+    lines.push(String::new());
+    lines.push(match session.state {
+        SessionState::Reconnecting => String::from("Connection lost, reconnecting"),
+        _ => String::from("The game starts when both seats are taken"),
+    });
+    lines.push(format!("<{}> - Leave", keymap.key_name(Action::Quit)));
+
+    draw_centered_box(&mut frame, &lines, "ONLINE GAME");
+    frame
+}
+
+fn player_name(session: &Session, seat: u8) -> String {
+    let name = match &session.players[seat as usize - 1] {
+        Some(name) => name.as_str(),
+        None => "waiting for a player",
+    };
+    if session.seat == Some(seat) {
+        format!("{} (you)", name)
+    } else {
+        name.to_string()
+    }
+}
+
+fn status_line(session: &Session, keymap: &Keymap) -> String {
+    let role = match session.seat {
+        Some(seat) => format!("You are Player {}", seat),
+        None => String::from("Watching"),
+    };
+    let state = match &session.state {
+        SessionState::Lobby | SessionState::Playing => String::new(),
+        SessionState::Paused { waiting_for } => {
+            format!("Waiting for Player {} to come back", waiting_for)
+        }
+        SessionState::Over { reason, .. } if session.players.contains(&None) => {
+            format!("{}, waiting for a new player", reason)
+        }
+        SessionState::Over { reason, .. } => format!("{}, next game starts soon", reason),
+        SessionState::Reconnecting => String::from("Connection lost, reconnecting"),
+    };
+
+    format!(
+        "ONLINE  {}  {}  <{}> leave",
+        role,
+        state,
+        keymap.key_name(Action::Quit)
+    )
+}
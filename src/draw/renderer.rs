@@ -0,0 +1,218 @@
+use std::io::Stdout;
+use std::time::Duration;
+
+use crate::{
+    config::Config,
+    draw::{
+        frame::{Frame, FrameBuffer},
+        game::game_frame,
+        game_over::game_over_screen_frame,
+        main_menu::main_menu_frame,
+        network::online_frame,
+        pause_menu::pause_screen_frame,
+        replay::replay_frame,
+        utils::full_clear,
+    },
+    game::Game,
+    game_loop::GameSummary,
+    keymap::Keymap,
+    menu::MainMenu,
+    net::client::Session,
+    replay::Playback,
+    window::{self, WindowDimensions},
+};
+
+/// Something the game's screens can be shown on.
+///
+/// Every screen is composed into a [`Frame`] first, so a backend only has to know how to
+/// show a whole frame.
+pub trait Renderer {
+    fn window_dimensions(&self) -> WindowDimensions;
+
+    /// Wipes the screen, the next frame is drawn in full.
+    fn clear(&mut self) -> std::io::Result<()>;
+
+    fn present(&mut self, frame: Frame) -> std::io::Result<()>;
+
+    /// Draws the board together with the score line, the fruit timer and the FPS counter.
+    fn draw_game(
+        &mut self,
+        game: &Game,
+        config: &Config,
+        last_delta: Duration,
+    ) -> std::io::Result<()> {
+        let frame = game_frame(game, config, self.window_dimensions(), last_delta);
+        self.present(frame)
+    }
+
+    /// Draws a recorded game that is being played back at `speed` times its speed.
+    fn draw_replay(
+        &mut self,
+        playback: &Playback,
+        speed: f64,
+        paused: bool,
+        config: &Config,
+        last_delta: Duration,
+    ) -> std::io::Result<()> {
+        let window_dim = self.window_dimensions();
+        let frame = replay_frame(playback, speed, paused, config, window_dim, last_delta);
+        self.present(frame)
+    }
+
+    /// Draws the lobby or the game of an online session.
+    fn draw_online(
+        &mut self,
+        session: &Session,
+        config: &Config,
+        last_delta: Duration,
+    ) -> std::io::Result<()> {
+        let frame = online_frame(session, config, self.window_dimensions(), last_delta);
+        self.present(frame)
+    }
+
+    fn draw_pause_menu(&mut self, keymap: &Keymap) -> std::io::Result<()> {
+        let frame = pause_screen_frame(keymap, self.window_dimensions());
+        self.present(frame)
+    }
+
+    fn draw_main_menu(&mut self, menu: &MainMenu, keymap: &Keymap) -> std::io::Result<()> {
+        let frame = main_menu_frame(menu, keymap, self.window_dimensions());
+        self.present(frame)
+    }
+
+    fn draw_game_over(
+        &mut self,
+        summary: &GameSummary,
+        name_input: Option<&str>,
+        keymap: &Keymap,
+    ) -> std::io::Result<()> {
+        let frame = game_over_screen_frame(summary, name_input, keymap, self.window_dimensions());
+        self.present(frame)
+    }
+}
+
+/// Draws to the terminal through crossterm, sending only what changed since the last frame.
+pub struct TerminalRenderer {
+    stdout: Stdout,
+    screen: FrameBuffer,
+}
+
+impl TerminalRenderer {
+    pub fn new(stdout: Stdout) -> TerminalRenderer {
+        TerminalRenderer {
+            stdout,
+            screen: FrameBuffer::default(),
+        }
+    }
+}
+
+impl Renderer for TerminalRenderer {
+    fn window_dimensions(&self) -> WindowDimensions {
+        window::window_dimensions()
+    }
+
+    fn clear(&mut self) -> std::io::Result<()> {
+        self.screen.invalidate();
+        full_clear(&mut self.stdout)
+    }
+
+    fn present(&mut self, frame: Frame) -> std::io::Result<()> {
+        self.screen.present(frame, &mut self.stdout)
+    }
+}
+
+/// Writes every frame as plain text, one line per row followed by an empty line.
+#[cfg(test)]
+pub struct TextRenderer<W: std::io::Write> {
+    out: W,
+    dimensions: WindowDimensions,
+}
+
+#[cfg(test)]
+impl<W: std::io::Write> TextRenderer<W> {
+    pub fn new(out: W, dimensions: WindowDimensions) -> TextRenderer<W> {
+        TextRenderer { out, dimensions }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+#[cfg(test)]
+impl<W: std::io::Write> Renderer for TextRenderer<W> {
+    fn window_dimensions(&self) -> WindowDimensions {
+        self.dimensions
+    }
+
+    fn clear(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    fn present(&mut self, frame: Frame) -> std::io::Result<()> {
+        for line in frame.lines() {
+            writeln!(self.out, "{}", line.trim_end())?;
+        }
+        writeln!(self.out)?;
+        self.out.flush()
+    }
+}
+
+/// Keeps the frames in memory instead of showing them, for looking at them in tests.
+#[cfg(test)]
+pub struct MemoryRenderer {
+    dimensions: WindowDimensions,
+    frame: Option<Frame>,
+    pub frames_presented: usize,
+}
+
+#[cfg(test)]
+impl MemoryRenderer {
+    pub fn new(dimensions: WindowDimensions) -> MemoryRenderer {
+        MemoryRenderer {
+            dimensions,
+            frame: None,
+            frames_presented: 0,
+        }
+    }
+
+    /// The frame that was presented last.
+    pub fn frame(&self) -> Option<&Frame> {
+        self.frame.as_ref()
+    }
+}
+
+#[cfg(test)]
+impl Renderer for MemoryRenderer {
+    fn window_dimensions(&self) -> WindowDimensions {
+        self.dimensions
+    }
+
+    fn clear(&mut self) -> std::io::Result<()> {
+        self.frame = None;
+        Ok(())
+    }
+
+    fn present(&mut self, frame: Frame) -> std::io::Result<()> {
+        self.frame = Some(frame);
+        self.frames_presented += 1;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_renderer_writes_plain_rows() {
+        let mut renderer = TextRenderer::new(vec![], (12, 40));
+        renderer.draw_pause_menu(&Keymap::default()).unwrap();
+
+        let text = String::from_utf8(renderer.into_inner()).unwrap();
+        assert!(!text.contains('\x1b'));
+        assert_eq!(text.lines().count(), 13);
+        assert!(text.contains("PAUSED"));
+        assert!(text.contains("<esc> - Resume game"));
+    }
+}
@@ -0,0 +1,65 @@
+use std::time::Duration;
+
+use crate::{
+    config::Config,
+    draw::frame::Frame,
+    draw::game::{game_frame, is_window_big_enough},
+    draw::utils::{background_frame, background_style},
+    keymap::{Action, Keymap},
+    replay::Playback,
+    window::WindowDimensions,
+};
+
+/// The game screen of a playback, with its state and controls on the bottom line.
+pub fn replay_frame(
+    playback: &Playback,
+    speed: f64,
+    paused: bool,
+    config: &Config,
+    window_dim: WindowDimensions,
+    last_delta: Duration,
+) -> Frame {
+    let game = &playback.game;
+    let mut frame = if is_window_big_enough(game, window_dim) {
+        game_frame(game, config, window_dim, last_delta)
+    } else {
+        let mut frame = background_frame(window_dim);
+        frame.print(0, 0, "Window too small", background_style());
+        frame
+    };
+
+    frame.print(
+        window_dim.0.saturating_sub(1),
+        0,
+        &status_line(playback, speed, paused, &config.keymap),
+        background_style(),
+    );
+    frame
+}
+
+fn status_line(playback: &Playback, speed: f64, paused: bool, keymap: &Keymap) -> String {
+    let state = if playback.is_finished() {
+        match playback.verify() {
+            Ok(()) => "finished, score matches the recording",
+            Err(_) => "finished, SCORE DIFFERS from the recording",
+        }
+    } else if paused {
+        "paused"
+    } else {
+        "playing"
+    };
+
+    format!(
+        "REPLAY {}x  move {}/{}  {}  <{}> pause  <{}> step  <{}/{}> speed  <{}> end  <{}> quit",
+        speed,
+        playback.game.steps,
+        playback.total_steps(),
+        state,
+        keymap.key_name(Action::Pause),
+        keymap.key_name(Action::Right),
+        keymap.key_name(Action::Down),
+        keymap.key_name(Action::Up),
+        keymap.key_name(Action::Confirm),
+        keymap.key_name(Action::Quit),
+    )
+}
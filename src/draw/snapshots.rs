@@ -0,0 +1,262 @@
+//! Golden-file tests for the rendered screens.
+//!
+//! Every snapshot in `src/draw/snapshots/` shows the symbols of a frame, then the same
+//! frame with one letter per style, then which style every letter stands for. Run the
+//! tests with `UPDATE_SNAPSHOTS=1` to write the current output after an intended change,
+//! and review the diff.
+use crossterm::style::{Attribute, ContentStyle};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::config::{Config, Theme};
+use crate::direction::Direction;
+use crate::draw::frame::Frame;
+use crate::draw::renderer::{MemoryRenderer, Renderer};
+use crate::game::Game;
+use crate::keymap::Keymap;
+use crate::level::Level;
+use crate::net::client::Session;
+use crate::replay::{Playback, Replay};
+use crate::settings::{GameSettings, Goal, WallMode};
+use crate::window::WindowDimensions;
+
+fn config(show_fps_counter: bool) -> Config {
+    Config {
+        show_fps_counter,
+        input_buffer: 3,
+        theme: Theme::default(),
+        keymap: Keymap::default(),
+        autopilot: None,
+    }
+}
+
+fn settings(level: Level, walls: WallMode, goal: Option<Goal>) -> GameSettings {
+    GameSettings {
+        goal,
+        ..GameSettings::for_test(level, walls)
+    }
+}
+
+fn game(level: Level, walls: WallMode, goal: Option<Goal>) -> Game {
+    Game::new(&settings(level, walls, goal), 7)
+}
+
+fn render(
+    window_dim: WindowDimensions,
+    draw: impl FnOnce(&mut MemoryRenderer) -> std::io::Result<()>,
+) -> Frame {
+    let mut renderer = MemoryRenderer::new(window_dim);
+    draw(&mut renderer).unwrap();
+    renderer.frame().unwrap().clone()
+}
+
+fn describe_style(style: &ContentStyle) -> String {
+    let mut description = format!(
+        "fg {:?}, bg {:?}",
+        style.foreground_color, style.background_color
+    );
+    for attribute in Attribute::iterator().filter(|a| style.attributes.has(*a)) {
+        description.push_str(&format!(", {:?}", attribute));
+    }
+    description
+}
+
+/// Symbols, a style letter for every cell, and the legend of the style letters.
+fn snapshot(frame: &Frame) -> String {
+    let mut styles: Vec<ContentStyle> = vec![];
+    let mut symbols = String::new();
+    let mut style_grid = String::new();
+
+    for row in 0..frame.dimensions.0 {
+        for col in 0..frame.dimensions.1 {
+            let cell = frame.get(row, col);
+            let index = match styles.iter().position(|style| *style == cell.style) {
+                Some(index) => index,
+                None => {
+                    styles.push(cell.style);
+                    styles.len() - 1
+                }
+            };
+            symbols.push(cell.symbol);
+            style_grid.push((b'a' + index as u8) as char);
+        }
+        symbols.push_str("|\n");
+        style_grid.push('\n');
+    }
+
+    let legend: String = styles
+        .iter()
+        .enumerate()
+        .map(|(i, style)| format!("{}: {}\n", (b'a' + i as u8) as char, describe_style(style)))
+        .collect();
+
+    format!("{}\n{}\n{}", symbols, style_grid, legend)
+}
+
+fn assert_snapshot(name: &str, frame: &Frame) {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "src", "draw", "snapshots", name]
+        .iter()
+        .collect::<PathBuf>()
+        .with_extension("txt");
+    let actual = snapshot(frame);
+
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        std::fs::write(&path, &actual).unwrap();
+        return;
+    }
+
+    let expected = std::fs::read_to_string(&path).unwrap_or_else(|_| {
+        panic!(
+            "missing snapshot {}, run with UPDATE_SNAPSHOTS=1 to create it",
+            path.display()
+        )
+    });
+    assert!(
+        expected == actual,
+        "snapshot {} differs, run with UPDATE_SNAPSHOTS=1 to update it\n\nexpected:\n{}\nactual:\n{}",
+        name,
+        expected,
+        actual
+    );
+}
+
+#[test]
+fn board_in_the_smallest_window() {
+    let game = game(Level::empty((4, 8)), WallMode::Solid, None);
+    let frame = render((8, 12), |r| {
+        r.draw_game(&game, &config(false), Duration::ZERO)
+    });
+
+    assert_snapshot("board_smallest_window", &frame);
+}
+
+#[test]
+fn board_centered_in_an_odd_sized_window() {
+    let game = game(Level::empty((5, 9)), WallMode::Wrap, None);
+    let frame = render((14, 25), |r| {
+        r.draw_game(&game, &config(false), Duration::ZERO)
+    });
+
+    assert_snapshot("board_odd_window", &frame);
+}
+
+#[test]
+fn board_with_obstacles_after_a_few_moves() {
+    let level = Level::parse(
+        "pillar",
+        "#########\n#.......#\n#..>....#\n#....##.#\n#########\n",
+    )
+    .unwrap();
+    let mut game = game(level, WallMode::Wrap, None);
+    game.step();
+    game.change_direction(Direction::Down);
+    game.step();
+    let frame = render((11, 15), |r| {
+        r.draw_game(&game, &config(false), Duration::ZERO)
+    });
+
+    assert_snapshot("board_obstacles", &frame);
+}
+
+#[test]
+fn score_line_with_a_goal_and_fruit_timer() {
+    let mut game = game(
+        Level::empty((4, 12)),
+        WallMode::Solid,
+        Some(Goal::Length(10)),
+    );
+    game.score = 15;
+    game.fruit = Some(((3, 11), 12));
+    let frame = render((10, 40), |r| {
+        r.draw_game(&game, &config(false), Duration::ZERO)
+    });
+
+    assert_snapshot("score_goal_fruit", &frame);
+}
+
+#[test]
+fn blinking_fruit() {
+    let mut game = game(Level::empty((4, 8)), WallMode::Wrap, None);
+    game.fruit = Some(((2, 5), 10));
+    let frame = render((8, 20), |r| {
+        r.draw_game(&game, &config(false), Duration::ZERO)
+    });
+
+    assert_snapshot("fruit_blinking", &frame);
+}
+
+#[test]
+fn fps_counter() {
+    let game = game(Level::empty((4, 8)), WallMode::Solid, None);
+    let frame = render((9, 16), |r| {
+        r.draw_game(&game, &config(true), Duration::from_millis(20))
+    });
+
+    assert_snapshot("fps_counter", &frame);
+}
+
+#[test]
+fn versus_board_with_both_scores() {
+    let mut settings = settings(Level::empty((5, 10)), WallMode::Solid, None);
+    settings.versus = true;
+    let mut game = Game::new(&settings, 7);
+    game.step();
+    game.score = 10;
+    let frame = render((11, 30), |r| {
+        r.draw_game(&game, &config(false), Duration::ZERO)
+    });
+
+    assert_snapshot("versus_board", &frame);
+}
+
+#[test]
+fn online_lobby_with_one_free_seat() {
+    let mut session = Session::new();
+    session.seat = Some(1);
+    session.players = [Some(String::from("ada")), None];
+    session.spectators = 2;
+    let frame = render((16, 56), |r| {
+        r.draw_online(&session, &config(false), Duration::ZERO)
+    });
+
+    assert_snapshot("online_lobby", &frame);
+}
+
+#[test]
+fn pause_menu_in_a_large_window() {
+    let frame = render((14, 40), |r| r.draw_pause_menu(&Keymap::default()));
+
+    assert_snapshot("pause_menu_large", &frame);
+}
+
+#[test]
+fn pause_menu_in_a_window_that_just_fits() {
+    let frame = render((10, 25), |r| r.draw_pause_menu(&Keymap::default()));
+
+    assert_snapshot("pause_menu_just_fits", &frame);
+}
+
+#[test]
+fn pause_menu_falls_back_in_a_small_window() {
+    let frame = render((9, 25), |r| r.draw_pause_menu(&Keymap::default()));
+    assert_snapshot("pause_menu_too_short", &frame);
+
+    let frame = render((10, 24), |r| r.draw_pause_menu(&Keymap::default()));
+    assert_snapshot("pause_menu_too_narrow", &frame);
+}
+
+#[test]
+fn replay_status_line() {
+    let settings = settings(Level::empty((4, 8)), WallMode::Wrap, None);
+    let mut game = Game::new(&settings, 7);
+    for _ in 0..6 {
+        game.step();
+    }
+    let mut playback = Playback::new(Replay::new(&settings, &game));
+    playback.step();
+    let frame = render((9, 100), |r| {
+        r.draw_replay(&playback, 0.5, true, &config(false), Duration::ZERO)
+    });
+
+    assert_snapshot("replay_status", &frame);
+}
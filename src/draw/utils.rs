@@ -1,10 +1,66 @@
 use crossterm::{
     execute,
+    style::{ContentStyle, Stylize},
     terminal::{Clear, ClearType},
 };
 use std::io::Stdout;
 
+use crate::{
+    consts,
+    draw::frame::{Cell, Frame},
+    window::WindowDimensions,
+};
+
 pub fn full_clear(stdout: &mut Stdout) -> std::io::Result<()> {
     execute!(stdout, Clear(ClearType::All), Clear(ClearType::Purge))?;
     Ok(())
 }
+
+pub fn background_style() -> ContentStyle {
+    ContentStyle::new()
+        .with(consts::BACKGROUND_TEXT_COLOR)
+        .on(consts::BACKGROUND_COLOR)
+}
+
+/// An empty frame filling the whole window.
+pub fn background_frame(window_dim: WindowDimensions) -> Frame {
+    Frame::new(window_dim, Cell::new(' ', background_style()))
+}
+
+/// Draws `content` in the middle of the frame. If the frame is too small for it, only
+/// `fallback` is drawn in the top left corner.
+pub fn draw_centered_box(frame: &mut Frame, content: &[String], fallback: &str) {
+    let window_dim = frame.dimensions;
+    let (rows, cols) = box_dimensions(content);
+
+    if window_dim.0 < rows || window_dim.1 < cols {
+        frame.print(0, 0, fallback, background_style());
+        return;
+    }
+
+    // The content goes inside the box, past its padding and border
+    let starting_row = (window_dim.0 - rows) / 2 + BOX_MARGIN;
+    let starting_col = (window_dim.1 - cols) / 2 + BOX_MARGIN;
+
+    content.iter().enumerate().for_each(|(i, line)| {
+        frame.print(
+            starting_row + i as u16,
+            starting_col,
+            line,
+            background_style(),
+        )
+    });
+}
+
+const BOX_BORDER_WIDTH: u16 = 1;
+const BOX_INNER_PADDING: u16 = 1;
+const BOX_OUTER_PADDING: u16 = 1;
+/// Space between the edge of a box and its content, on each side
+const BOX_MARGIN: u16 = BOX_BORDER_WIDTH + BOX_INNER_PADDING + BOX_OUTER_PADDING;
+
+fn box_dimensions(lines: &[String]) -> (u16, u16) {
+    (
+        lines.len() as u16 + BOX_MARGIN * 2,
+        lines.iter().map(|l| l.chars().count()).max().unwrap() as u16 + BOX_MARGIN * 2,
+    )
+}
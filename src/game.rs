@@ -1,48 +1,155 @@
-use rand::Rng;
-use std::cmp::max;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use std::cmp::{max, Ordering};
 use std::collections::HashSet;
+use std::fmt;
 use std::time::Duration;
 
 use crate::board::BoardPiece;
 use crate::direction::Direction;
+use crate::settings::{GameSettings, Goal, WallMode};
 use crate::snake::Snake;
+use crate::timestep::FixedTimestep;
 use crate::utils::manhattan_distance;
 
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub enum GameMode {
     Game,
     Pause,
 }
 
+/// Why a game ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameOver {
+    /// The snake ran into its own body.
+    SelfCollision,
+    /// The snake ran into a solid wall.
+    WallCollision,
+    /// The snake covers the whole board, so there is nowhere left to spawn food.
+    BoardFilled,
+    /// The goal of the level was reached.
+    LevelCleared,
+    /// One snake crashed in a versus game, so the other player wins.
+    Won(Player),
+    /// Both snakes crashed on the same move in a versus game.
+    Draw,
+}
+
+impl GameOver {
+    pub fn is_victory(&self) -> bool {
+        matches!(self, GameOver::BoardFilled | GameOver::LevelCleared)
+    }
+}
+
+impl fmt::Display for GameOver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let description = match self {
+            GameOver::Won(player) => return write!(f, "{} wins", player),
+            GameOver::Draw => "Both snakes crashed",
+            GameOver::SelfCollision => "The snake bit itself",
+            GameOver::WallCollision => "The snake hit a wall",
+            GameOver::BoardFilled => "The board is full",
+            GameOver::LevelCleared => "Level cleared",
+        };
+        f.write_str(description)
+    }
+}
+
+/// One of the players of a versus game.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Player {
+    One,
+    Two,
+}
+
+impl fmt::Display for Player {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Player::One => f.write_str("Player 1"),
+            Player::Two => f.write_str("Player 2"),
+        }
+    }
+}
+
+/// The second player's snake in a versus game.
+pub struct Rival {
+    snake: Snake,
+    pub score: u32,
+    just_ate: bool,
+}
+
+/// What happened during a single call to [`Game::step`].
+#[derive(Default, Debug)]
+pub struct StepOutcome {
+    /// The first player's snake moved onto a new field, instead of into a wall
+    pub moved: bool,
+    pub ate_food: bool,
+    pub ate_fruit: bool,
+    pub fruit_expired: bool,
+    pub game_over: Option<GameOver>,
+}
+
 pub struct Game {
     pub mode: GameMode,
+    /// The first player's snake, the only one outside versus games
     snake: Snake,
+    rival: Option<Rival>,
     pub dimensions: (u16, u16),
+    obstacles: HashSet<(u16, u16)>,
+    pub walls: WallMode,
     pub food: (u16, u16),
     pub fruit: Option<((u16, u16), u16)>,
     pub score: u32,
+    pub goal: Option<Goal>,
+    /// Time spent in the game mode, pauses excluded
+    pub time_played: Duration,
     just_ate: bool,
     moves_until_next_fruit: u8,
-    next_tick_in: Duration,
-    speed: Duration,
+    timestep: FixedTimestep,
     difficulty: u16,
+    pub seed: u64,
+    rng: StdRng,
+    /// Number of moves made so far
+    pub steps: u64,
+    /// Every change of direction, with the number of the move that made it
+    turns: Vec<(u64, Direction)>,
 }
 
 impl Game {
-    pub fn new(dimensions: (u16, u16), difficulty: u16) -> Game {
-        let difficulty = difficulty.clamp(1, 9);
+    /// Creates a new game. All random spawns are drawn from an RNG seeded with `seed`,
+    /// so two games created with the same arguments play out identically.
+    pub fn new(settings: &GameSettings, seed: u64) -> Game {
+        let difficulty = settings.difficulty.clamp(1, 9);
         let speed = Duration::from_millis(280 - 25 * difficulty as u64);
         let mut new_obj = Game {
             mode: GameMode::Game,
-            snake: Snake::new(vec![(0, 0), (0, 1), (0, 2)], Direction::Down),
-            dimensions,
+            snake: Snake::new(settings.level.snake.clone(), settings.level.direction),
+            rival: settings
+                .versus
+                .then(|| settings.level.rival_start())
+                .flatten()
+                .map(|(body, direction)| Rival {
+                    snake: Snake::new(body, direction),
+                    score: 0,
+                    just_ate: false,
+                }),
+            dimensions: settings.level.dimensions,
+            obstacles: settings.level.obstacles.clone(),
+            walls: settings.walls,
             food: (0, 0),
             fruit: None,
             score: 0,
+            goal: settings.goal,
+            time_played: Duration::ZERO,
             just_ate: false,
             moves_until_next_fruit: 120,
-            next_tick_in: speed,
-            speed,
+            timestep: FixedTimestep::new(speed),
             difficulty,
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+            steps: 0,
+            turns: vec![],
         };
         new_obj.spawn_food().unwrap();
 
@@ -57,86 +164,238 @@ impl Game {
         self.mode = GameMode::Game
     }
 
-    pub fn tick(&mut self, delta: Duration) -> Result<(), ()> {
-        if !self.can_tick(delta) {
-            self.next_tick_in -= delta;
-            return Ok(());
-        }
+    /// Advances the game clock by the time that passed since the last tick and returns
+    /// how many steps are due. The caller takes them with [`Game::step`].
+    pub fn tick(&mut self, delta: Duration) -> u32 {
+        self.time_played += delta;
+        self.timestep.advance(delta)
+    }
 
-        // FIXME: This mechanism does not work well in case of very low FPS
-        self.next_tick_in = self.speed;
+    /// Time between two moves at the game's speed.
+    pub fn step_interval(&self) -> Duration {
+        self.timestep.step()
+    }
 
+    /// Time left until the next step is due.
+    pub fn until_next_step(&self) -> Duration {
+        self.timestep.until_next_step()
+    }
+
+    /// Advances the game by exactly one move, independently of wall-clock time.
+    pub fn step(&mut self) -> StepOutcome {
+        let mut outcome = StepOutcome::default();
+
+        let previous_direction = self.snake.direction;
         let direction = self.snake.next_direction();
-        let head = self.snake.body.first().unwrap();
-        let mut next_pos: (u16, u16) = next_position(*head, direction, self.dimensions);
-        let head_next = next_pos;
-
-        if self.just_ate {
-            self.snake.body = [vec![next_pos], self.snake.body.clone()]
-                .iter()
-                .flat_map(|e| e.clone())
-                .collect::<Vec<(u16, u16)>>();
-            self.just_ate = false
-        } else {
-            self.snake.body = self
-                .snake
-                .body
-                .iter()
-                .map(|e| {
-                    let tmp = next_pos;
-                    next_pos = *e;
-                    tmp
-                })
-                .collect::<_>();
+        if direction != previous_direction {
+            self.turns.push((self.steps, direction));
+        }
+        self.steps += 1;
+
+        // Both snakes move at the same time. One that hits a wall stays where it is.
+        let head_next = field_ahead(
+            self.snake.body[0],
+            direction,
+            self.dimensions,
+            self.walls,
+            &self.obstacles,
+        );
+        if let Some(head) = head_next {
+            self.snake.advance(head, self.just_ate);
+            self.just_ate = false;
+            outcome.moved = true;
         }
+        let rival_crashed = match &mut self.rival {
+            Some(rival) => {
+                let direction = rival.snake.next_direction();
+                let head_next = field_ahead(
+                    rival.snake.body[0],
+                    direction,
+                    self.dimensions,
+                    self.walls,
+                    &self.obstacles,
+                );
+                if let Some(head) = head_next {
+                    rival.snake.advance(head, rival.just_ate);
+                    rival.just_ate = false;
+                }
+                let head = rival.snake.body[0];
+                head_next.is_none()
+                    || rival.snake.body[1..].contains(&head)
+                    || self.snake.body.contains(&head)
+            }
+            None => false,
+        };
+
+        let head = self.snake.body[0];
+        let crash = if head_next.is_none() {
+            Some(GameOver::WallCollision)
+        } else if self.snake.body[1..].contains(&head)
+            || self
+                .rival
+                .as_ref()
+                .is_some_and(|r| r.snake.body.contains(&head))
+        {
+            Some(GameOver::SelfCollision)
+        } else {
+            None
+        };
 
-        if self.snake.body[1..].contains(&head_next) {
-            return Err(());
+        outcome.game_over = match (&self.rival, crash, rival_crashed) {
+            (None, crash, _) => crash,
+            (Some(_), Some(_), true) => Some(GameOver::Draw),
+            (Some(_), Some(_), false) => Some(GameOver::Won(Player::Two)),
+            (Some(_), None, true) => Some(GameOver::Won(Player::One)),
+            (Some(_), None, false) => None,
+        };
+        if outcome.game_over.is_some() {
+            return outcome;
         }
 
-        if head_next == self.food {
-            self.score += self.difficulty as u32;
-            self.just_ate = true;
-            match self.spawn_food() {
-                Err(_) => return Err(()),
-                _ => {}
-            };
+        if let Some(player) = self.player_at(self.food) {
+            self.feed(player, self.difficulty as u32);
+            outcome.ate_food = true;
+            if self.spawn_food().is_err() {
+                outcome.game_over = Some(self.board_filled());
+                return outcome;
+            }
         }
 
         if let Some((fruit, remaining_moves)) = self.fruit {
             if remaining_moves == 0 {
-                self.moves_until_next_fruit = rand::thread_rng().gen_range(30..180);
+                self.moves_until_next_fruit = self.rng.gen_range(30..180);
                 self.fruit = None;
-            } else if head_next == fruit {
-                self.score += remaining_moves as u32 * self.difficulty as u32;
-                self.just_ate = true;
-                self.moves_until_next_fruit = rand::thread_rng().gen_range(30..180);
+                outcome.fruit_expired = true;
+            } else if let Some(player) = self.player_at(fruit) {
+                self.feed(player, remaining_moves as u32 * self.difficulty as u32);
+                self.moves_until_next_fruit = self.rng.gen_range(30..180);
                 self.fruit = None;
+                outcome.ate_fruit = true;
             } else {
                 self.fruit = Some((fruit, remaining_moves - 1));
             }
+        } else if self.moves_until_next_fruit == 0 {
+            self.spawn_fruit();
         } else {
-            if self.moves_until_next_fruit == 0 {
-                self.spawn_fruit();
-            } else {
-                self.moves_until_next_fruit -= 1;
+            self.moves_until_next_fruit -= 1;
+        }
+
+        if self.is_goal_reached() {
+            outcome.game_over = Some(GameOver::LevelCleared);
+        }
+
+        outcome
+    }
+
+    /// The player whose snake has its head at `pos`.
+    fn player_at(&self, pos: (u16, u16)) -> Option<Player> {
+        if self.snake.body[0] == pos {
+            Some(Player::One)
+        } else if self.rival.as_ref().is_some_and(|r| r.snake.body[0] == pos) {
+            Some(Player::Two)
+        } else {
+            None
+        }
+    }
+
+    /// Gives `points` to `player`, whose snake grows on the next move.
+    fn feed(&mut self, player: Player, points: u32) {
+        match (player, &mut self.rival) {
+            (Player::Two, Some(rival)) => {
+                rival.score += points;
+                rival.just_ate = true;
+            }
+            _ => {
+                self.score += points;
+                self.just_ate = true;
             }
         }
-        Ok(())
     }
 
-    fn can_tick(&self, delta: Duration) -> bool {
-        self.next_tick_in < delta
+    /// Heads of all snakes on the board.
+    fn heads(&self) -> Vec<(u16, u16)> {
+        let mut heads = vec![self.snake.body[0]];
+        heads.extend(self.rival.as_ref().map(|rival| rival.snake.body[0]));
+        heads
+    }
+
+    /// Direction of the snake's last move.
+    pub fn direction(&self) -> Direction {
+        self.snake.direction
+    }
+
+    /// Positions of the snake, head first.
+    pub fn snake_body(&self) -> &[(u16, u16)] {
+        &self.snake.body
+    }
+
+    /// Every change of direction so far, with the number of the move that made it.
+    pub fn turns(&self) -> &[(u64, Direction)] {
+        &self.turns
+    }
+
+    pub fn obstacles(&self) -> &HashSet<(u16, u16)> {
+        &self.obstacles
+    }
+
+    /// The second player's snake, in a versus game.
+    pub fn rival(&self) -> Option<&Rival> {
+        self.rival.as_ref()
+    }
+
+    /// Length of the snake, counting the segment it grows by after eating.
+    pub fn snake_length(&self) -> usize {
+        self.snake.body.len() + self.just_ate as usize
+    }
+
+    fn is_goal_reached(&self) -> bool {
+        match self.goal {
+            Some(Goal::Length(length)) => self.snake_length() >= length as usize,
+            Some(Goal::Score(score)) => self.score >= score,
+            None => false,
+        }
     }
 
     pub fn change_direction(&mut self, direction: Direction) {
         self.snake.change_direction(direction)
     }
 
+    /// Turns the second player's snake, in a versus game.
+    pub fn change_rival_direction(&mut self, direction: Direction) {
+        if let Some(rival) = &mut self.rival {
+            rival.snake.change_direction(direction)
+        }
+    }
+
+    /// Sets how many turns the players can queue ahead of the snakes' moves.
+    pub fn set_input_buffer(&mut self, size: usize) {
+        self.snake.set_input_buffer(size);
+        if let Some(rival) = &mut self.rival {
+            rival.snake.set_input_buffer(size);
+        }
+    }
+
+    /// How the game ends once the snakes leave no room for food. In a versus game, the
+    /// player with more points wins.
+    fn board_filled(&self) -> GameOver {
+        let Some(rival) = &self.rival else {
+            return GameOver::BoardFilled;
+        };
+        match self.score.cmp(&rival.score) {
+            Ordering::Greater => GameOver::Won(Player::One),
+            Ordering::Less => GameOver::Won(Player::Two),
+            Ordering::Equal => GameOver::Draw,
+        }
+    }
+
+    /// Moves the food to a free field. The fruit's field counts as free, so the fruit
+    /// makes way for the food if there is nowhere else left.
     fn spawn_food(&mut self) -> Result<(), ()> {
-        let candidate = self.element_spawn_candidate();
+        let candidate = self
+            .element_spawn_candidate()
+            .or_else(|| self.fruit.take().map(|(fruit, _)| fruit));
         match candidate {
-            Some(e) => self.food = e.clone(),
+            Some(e) => self.food = e,
             None => return Err(()),
         }
         Ok(())
@@ -144,7 +403,7 @@ impl Game {
 
     fn spawn_fruit(&mut self) {
         if let Some(candidate) = self.element_spawn_candidate() {
-            let distance = manhattan_distance(candidate, *self.snake.body.first().unwrap());
+            let distance = self.distance_to_nearest_head(candidate);
             let allowed_moves = max(distance * 2, 10);
 
             self.fruit = Some((candidate, allowed_moves));
@@ -152,92 +411,269 @@ impl Game {
     }
 
     fn element_spawn_candidate(&mut self) -> Option<(u16, u16)> {
-        let mut board_elements: HashSet<(u16, u16)> = (0..self.dimensions.0)
-            .flat_map(|row| {
-                (0..self.dimensions.1)
-                    .map(|col| (row, col))
-                    .collect::<Vec<_>>()
-            })
-            .collect();
-
-        for snake_piece in &self.snake.body {
-            board_elements.remove(&snake_piece);
+        let mut occupied: HashSet<(u16, u16)> = self.snake.body.iter().copied().collect();
+        if let Some(rival) = &self.rival {
+            occupied.extend(&rival.snake.body);
         }
+        occupied.extend(&self.obstacles);
 
         if let Some((fruit, _)) = self.fruit {
-            board_elements.remove(&fruit);
+            occupied.insert(fruit);
         }
 
-        board_elements.remove(&self.food);
+        occupied.insert(self.food);
+
+        // Collected in row-major order, so the result only depends on the RNG state
+        let board_elements: Vec<(u16, u16)> = (0..self.dimensions.0)
+            .flat_map(|row| (0..self.dimensions.1).map(move |col| (row, col)))
+            .filter(|e| !occupied.contains(e))
+            .collect();
 
-        let snake_head = &self.snake.body.first().unwrap();
+        let candidates: Vec<(u16, u16)> = board_elements
+            .choose_multiple(&mut self.rng, 3)
+            .copied()
+            .collect();
 
         // Sometime random is way too close to the head
-        board_elements
-            .iter()
-            .take(3)
-            .map(|e| e.clone())
-            .max_by(|a, b| {
-                manhattan_distance(*a, **snake_head).cmp(&manhattan_distance(*b, **snake_head))
-            })
+        candidates
+            .into_iter()
+            .max_by_key(|candidate| self.distance_to_nearest_head(*candidate))
+    }
+
+    fn distance_to_nearest_head(&self, pos: (u16, u16)) -> u16 {
+        self.heads()
+            .into_iter()
+            .map(|head| manhattan_distance(pos, head))
+            .min()
+            .unwrap()
     }
 
     pub fn board_pieces(&self) -> Vec<Vec<BoardPiece>> {
         let mut res =
             vec![vec![BoardPiece::Empty; self.dimensions.1 as usize]; self.dimensions.0 as usize];
 
+        for (row, col) in &self.obstacles {
+            res[*row as usize][*col as usize] = BoardPiece::Wall;
+        }
+
         res[self.food.0 as usize][self.food.1 as usize] = BoardPiece::Food;
 
         if let Some(((row, col), _)) = self.fruit {
             res[row as usize][col as usize] = BoardPiece::Fruit;
         }
 
-        for (i, snake_piece) in self.snake.body.iter().enumerate() {
-            let piece: BoardPiece = if i == 0 {
-                BoardPiece::SnakeHead
-            } else {
-                BoardPiece::Snake
-            };
-            res[snake_piece.0 as usize][snake_piece.1 as usize] = piece;
+        let snakes = [
+            (Player::One, Some(&self.snake)),
+            (Player::Two, self.rival.as_ref().map(|r| &r.snake)),
+        ];
+        for (player, snake) in snakes {
+            for (i, snake_piece) in snake.iter().flat_map(|snake| snake.body.iter()).enumerate() {
+                let piece: BoardPiece = if i == 0 {
+                    BoardPiece::SnakeHead(player)
+                } else {
+                    BoardPiece::Snake(player)
+                };
+                res[snake_piece.0 as usize][snake_piece.1 as usize] = piece;
+            }
         }
 
         res
     }
 }
 
-fn next_position(
+/// The field a snake moves to from `head`, or `None` if it hits a wall or an obstacle.
+fn field_ahead(
+    head: (u16, u16),
+    direction: Direction,
+    dimensions: (u16, u16),
+    walls: WallMode,
+    obstacles: &HashSet<(u16, u16)>,
+) -> Option<(u16, u16)> {
+    next_position(head, direction, dimensions, walls).filter(|pos| !obstacles.contains(pos))
+}
+
+/// Returns the field next to `pos` in `direction`, or `None` if that would leave
+/// the board through a solid wall.
+pub fn next_position(
     pos: (u16, u16),
     direction: Direction,
     board_dimensions: (u16, u16),
-) -> (u16, u16) {
+    walls: WallMode,
+) -> Option<(u16, u16)> {
+    let wraps = walls == WallMode::Wrap;
     match direction {
         Direction::Up => {
-            if pos.0 == 0 {
-                (board_dimensions.0 - 1, pos.1)
+            if pos.0 > 0 {
+                Some((pos.0 - 1, pos.1))
             } else {
-                (pos.0 - 1, pos.1)
+                wraps.then_some((board_dimensions.0 - 1, pos.1))
             }
         }
         Direction::Down => {
-            if pos.0 == board_dimensions.0 - 1 {
-                (0, pos.1)
+            if pos.0 < board_dimensions.0 - 1 {
+                Some((pos.0 + 1, pos.1))
             } else {
-                (pos.0 + 1, pos.1)
+                wraps.then_some((0, pos.1))
             }
         }
         Direction::Left => {
-            if pos.1 == 0 {
-                (pos.0, board_dimensions.1 - 1)
+            if pos.1 > 0 {
+                Some((pos.0, pos.1 - 1))
             } else {
-                (pos.0, pos.1 - 1)
+                wraps.then_some((pos.0, board_dimensions.1 - 1))
             }
         }
         Direction::Right => {
-            if pos.1 == board_dimensions.1 - 1 {
-                (pos.0, 0)
+            if pos.1 < board_dimensions.1 - 1 {
+                Some((pos.0, pos.1 + 1))
             } else {
-                (pos.0, pos.1 + 1)
+                wraps.then_some((pos.0, 0))
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::Level;
+
+    /// A single-player game on `map`, with the food out of the snake's way.
+    fn solo_game(map: &str, walls: WallMode) -> Game {
+        let settings = GameSettings::for_test(Level::parse("solo", map).unwrap(), walls);
+        let mut game = Game::new(&settings, 1);
+        game.food = (0, 0);
+        game
+    }
+
+    #[test]
+    fn step_moves_the_snake_one_field() {
+        let mut game = solo_game("......\n..>...\n......\n", WallMode::Solid);
+
+        let outcome = game.step();
+
+        assert!(outcome.moved);
+        assert!(!outcome.ate_food && outcome.game_over.is_none());
+        assert_eq!(game.snake.body, vec![(1, 3), (1, 2), (1, 1)]);
+        assert_eq!(game.steps, 1);
+    }
+
+    #[test]
+    fn eating_food_scores_and_grows_the_snake_on_the_next_move() {
+        let mut game = solo_game("......\n..>...\n......\n", WallMode::Solid);
+        game.food = (1, 3);
+
+        let outcome = game.step();
+        assert!(outcome.ate_food);
+        assert_eq!(game.score, 5);
+        assert_eq!(game.snake.body.len(), 3);
+        assert_eq!(game.snake_length(), 4);
+        assert_ne!(game.food, (1, 3));
+
+        game.food = (0, 0);
+        game.step();
+        assert_eq!(game.snake.body, vec![(1, 4), (1, 3), (1, 2), (1, 1)]);
+    }
+
+    #[test]
+    fn food_takes_the_place_of_the_fruit_on_the_last_free_field() {
+        let mut game = solo_game("#####\n..>..\n#####\n", WallMode::Solid);
+        game.food = (1, 3);
+        game.fruit = Some(((1, 4), 9));
+        game.just_ate = true;
+
+        let outcome = game.step();
+        assert!(outcome.ate_food);
+        assert_eq!(outcome.game_over, None);
+        assert_eq!(game.food, (1, 4));
+        assert_eq!(game.fruit, None);
+
+        assert_eq!(game.step().game_over, Some(GameOver::BoardFilled));
+    }
+
+    #[test]
+    fn solid_walls_stop_the_snake() {
+        let mut game = solo_game("...\n..>\n...\n", WallMode::Solid);
+
+        let outcome = game.step();
+
+        assert!(!outcome.moved);
+        assert_eq!(outcome.game_over, Some(GameOver::WallCollision));
+        assert_eq!(game.snake.body[0], (1, 2));
+    }
+
+    #[test]
+    fn wrapping_walls_let_the_snake_through() {
+        let mut game = solo_game("...\n..>\n...\n", WallMode::Wrap);
+
+        let outcome = game.step();
+
+        assert!(outcome.moved && outcome.game_over.is_none());
+        assert_eq!(game.snake.body[0], (1, 0));
+    }
+
+    #[test]
+    fn running_into_its_own_body_ends_the_game() {
+        let mut game = solo_game("......\n......\n....>.\n......\n", WallMode::Solid);
+        game.snake.body = vec![(1, 1), (1, 2), (2, 2), (2, 1), (3, 1)];
+        game.snake.direction = Direction::Left;
+        game.change_direction(Direction::Down);
+
+        let outcome = game.step();
+
+        assert!(outcome.moved);
+        assert_eq!(outcome.game_over, Some(GameOver::SelfCollision));
+    }
+
+    fn versus_game(map: &str) -> Game {
+        let settings = GameSettings {
+            versus: true,
+            ..GameSettings::for_test(Level::parse("duel", map).unwrap(), WallMode::Solid)
+        };
+        let mut game = Game::new(&settings, 1);
+        game.food = (0, 0);
+        game
+    }
+
+    #[test]
+    fn rival_starts_opposite_the_first_snake() {
+        let game = versus_game("........\n..>.....\n........\n........\n");
+
+        assert_eq!(game.snake.body, vec![(1, 2), (1, 1), (1, 0)]);
+        let rival = game.rival().unwrap();
+        assert_eq!(rival.snake.body, vec![(2, 5), (2, 6), (2, 7)]);
+        assert_eq!(rival.snake.direction, Direction::Left);
+    }
+
+    #[test]
+    fn head_on_collision_is_a_draw() {
+        // Both heads move onto the same field
+        let mut game = versus_game(".......\n..>....\n.......\n");
+        assert_eq!(game.step().game_over, Some(GameOver::Draw));
+
+        // The heads swap places
+        let mut game = versus_game("........\n...>....\n........\n");
+        assert_eq!(game.step().game_over, Some(GameOver::Draw));
+    }
+
+    #[test]
+    fn running_into_the_other_snake_loses() {
+        let mut game = versus_game("........\n..>.....\n........\n........\n");
+        game.change_direction(Direction::Down);
+        game.step();
+        game.step();
+        game.change_direction(Direction::Right);
+
+        assert_eq!(game.step().game_over, Some(GameOver::Won(Player::One)));
+    }
+
+    #[test]
+    fn food_goes_to_the_snake_that_eats_it() {
+        let mut game = versus_game("........\n..>.....\n........\n........\n");
+        game.food = (2, 4);
+
+        assert!(game.step().ate_food);
+        assert_eq!(game.score, 0);
+        assert_eq!(game.rival().unwrap().score, 5);
+    }
+}
@@ -1,145 +1,636 @@
+use crate::autopilot::{Autopilot, Controller, Forfeit};
+use crate::campaign::Campaign;
 use crate::config::Config;
-use crate::consts::FPS_LIMIT;
 use crate::direction;
 use crate::draw;
-use crate::draw::pause_menu::draw_pause_screen;
+use crate::draw::renderer::Renderer;
 use crate::game::Game;
 use crate::game::GameMode;
-use crate::window;
+use crate::game::GameOver;
+use crate::input::{collect_input, EventSource};
+use crate::keymap::Action;
+use crate::menu::{MainMenu, MenuChoice};
+use crate::replay::Playback;
+use crate::timestep::FixedTimestep;
 
-use crossterm::event::{poll, read, Event, KeyCode};
-use std::thread;
+use crossterm::event::{Event, KeyCode, KeyEventKind, KeyModifiers};
+use std::fmt;
 use std::time::{Duration, Instant};
 
 pub enum GameLoopSignal {
     Exit,
-    GameOver,
+    GameOver(GameEnd),
     Ok,
 }
 
+/// Why a game in the game loop ended.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameEnd {
+    GameOver(GameOver),
+    /// The autopilot gave up.
+    Forfeit(Forfeit),
+}
+
+impl fmt::Display for GameEnd {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GameEnd::GameOver(reason) => reason.fmt(f),
+            GameEnd::Forfeit(Forfeit::TooSlow) => f.write_str("The bot took too long to move"),
+            GameEnd::Forfeit(Forfeit::InvalidMove) => f.write_str("The bot sent an invalid move"),
+            GameEnd::Forfeit(Forfeit::Gone) => f.write_str("The bot stopped playing"),
+        }
+    }
+}
+
+/// Final results of a game, shown on the game over screen.
+pub struct GameSummary {
+    pub reason: GameEnd,
+    pub score: u32,
+    pub length: usize,
+    /// The second player's score, in a versus game
+    pub rival_score: Option<u32>,
+    pub time_played: Duration,
+    pub best: Option<u32>,
+}
+
+pub enum GameOverChoice {
+    Restart,
+    Menu,
+    Quit,
+}
+
+/// Runs the game until it ends. In a campaign, clearing a level loads the next one
+/// instead of ending the game.
+///
+/// The loop sleeps until either the next step is due or the player does something, and
+/// only redraws the screen when something on it has changed.
 pub fn start_game(
     game: &mut Game,
+    mut campaign: Option<&mut Campaign>,
     config: &Config,
-    stdout: &mut std::io::Stdout,
+    events: &mut dyn EventSource,
+    renderer: &mut dyn Renderer,
 ) -> std::io::Result<GameLoopSignal> {
-    draw::utils::full_clear(stdout)?;
+    renderer.clear()?;
+    game.set_input_buffer(config.input_buffer);
+    let keymap = if game.rival().is_some() {
+        config.keymap.versus()
+    } else {
+        config.keymap.clone()
+    };
+    let mut controller = config
+        .autopilot
+        .as_ref()
+        .map(Autopilot::controller)
+        .transpose()?;
 
     let mut last_frame_time = Instant::now();
+    let mut last_draw_time = last_frame_time;
+    let mut needs_redraw = true;
     loop {
-        let now = Instant::now();
-        let delta = now - last_frame_time;
+        if needs_redraw {
+            let now = Instant::now();
+            draw_frame(game, config, now - last_draw_time, renderer)?;
+            last_draw_time = now;
+            needs_redraw = false;
+        }
 
-        // FPS limiter
-        let frame_time = Duration::from_nanos(1000 * 1_000_000 / FPS_LIMIT);
-        if delta < frame_time {
-            thread::sleep(frame_time - delta);
-            continue;
+        // Nothing changes on its own while the game is paused
+        let timeout = match game.mode {
+            GameMode::Game => Some(game.until_next_step()),
+            GameMode::Pause => None,
+        };
+        let mut input = collect_input(events, &keymap, timeout)?;
+        // The autopilot steers, the player can only pause and quit
+        if controller.is_some() {
+            input.actions.retain(|action| {
+                !matches!(
+                    action,
+                    Action::Up | Action::Down | Action::Left | Action::Right
+                )
+            });
         }
 
+        let now = Instant::now();
+        let delta = now - last_frame_time;
         last_frame_time = now;
 
-        let loop_res = match game.mode {
-            GameMode::Game => loop_game_mode(game, config, delta, stdout),
-            GameMode::Pause => loop_pause_mode(game, stdout),
+        let mode_before = game.mode;
+        let signal = apply_actions(game, &input.actions);
+        if input.resized.is_some() || game.mode != mode_before {
+            renderer.clear()?;
+            needs_redraw = true;
+        }
+
+        // Time spent in the pause menu does not count towards the next step
+        let signal = match signal {
+            GameLoopSignal::Ok if mode_before == GameMode::Game => {
+                let steps = game.tick(delta);
+                needs_redraw |= steps > 0;
+                play_steps(game, steps, controller.as_deref_mut())
+            }
+            signal => signal,
         };
 
-        match loop_res {
-            Ok(signal) => match signal {
-                GameLoopSignal::Ok => continue,
-                _ => return Ok(signal),
-            },
-            Err(e) => {
-                return Err(e);
+        match signal {
+            GameLoopSignal::Ok => continue,
+            GameLoopSignal::GameOver(GameEnd::GameOver(GameOver::LevelCleared))
+                if campaign.as_deref_mut().is_some_and(Campaign::advance) =>
+            {
+                let settings = campaign.as_deref().unwrap().settings();
+                *game = Game::new(&settings, game.seed.wrapping_add(1));
+                game.set_input_buffer(config.input_buffer);
+                controller = config
+                    .autopilot
+                    .as_ref()
+                    .map(Autopilot::controller)
+                    .transpose()?;
+                renderer.clear()?;
+                needs_redraw = true;
             }
+            _ => return Ok(signal),
         };
     }
 }
 
-fn loop_game_mode(
+/// Takes `steps` moves. If there is a `controller`, it steers the snake before every move.
+fn play_steps(
+    game: &mut Game,
+    steps: u32,
+    mut controller: Option<&mut (dyn Controller + 'static)>,
+) -> GameLoopSignal {
+    for _ in 0..steps {
+        if let Some(controller) = controller.as_deref_mut() {
+            match controller.next_direction(game) {
+                Ok(direction) => game.change_direction(direction),
+                Err(forfeit) => return GameLoopSignal::GameOver(GameEnd::Forfeit(forfeit)),
+            }
+        }
+        if let Some(reason) = game.step().game_over {
+            return GameLoopSignal::GameOver(GameEnd::GameOver(reason));
+        }
+    }
+
+    GameLoopSignal::Ok
+}
+
+/// Applies the player's actions in order, switching between the game and the pause
+/// menu as they go.
+fn apply_actions(game: &mut Game, actions: &[Action]) -> GameLoopSignal {
+    for action in actions {
+        match (game.mode, action) {
+            (GameMode::Game, Action::Up) => game.change_direction(direction::Direction::Up),
+            (GameMode::Game, Action::Down) => game.change_direction(direction::Direction::Down),
+            (GameMode::Game, Action::Left) => game.change_direction(direction::Direction::Left),
+            (GameMode::Game, Action::Right) => game.change_direction(direction::Direction::Right),
+            (GameMode::Game, Action::Player2Up) => {
+                game.change_rival_direction(direction::Direction::Up)
+            }
+            (GameMode::Game, Action::Player2Down) => {
+                game.change_rival_direction(direction::Direction::Down)
+            }
+            (GameMode::Game, Action::Player2Left) => {
+                game.change_rival_direction(direction::Direction::Left)
+            }
+            (GameMode::Game, Action::Player2Right) => {
+                game.change_rival_direction(direction::Direction::Right)
+            }
+            (GameMode::Game, Action::Pause) => game.pause_game(),
+            (GameMode::Pause, Action::Pause) => game.unpause_game(),
+            (GameMode::Pause, Action::Quit) => return GameLoopSignal::Exit,
+            _ => {}
+        }
+    }
+
+    GameLoopSignal::Ok
+}
+
+/// Draws the game or the pause menu. Pauses the game if the window got too small for
+/// the board.
+fn draw_frame(
     game: &mut Game,
     config: &Config,
-    tick_delta: Duration,
-    stdout: &mut std::io::Stdout,
-) -> std::io::Result<GameLoopSignal> {
-    let window_dim = window::window_dimensions();
+    last_delta: Duration,
+    renderer: &mut dyn Renderer,
+) -> std::io::Result<()> {
+    let window_dim = renderer.window_dimensions();
 
-    match game.tick(tick_delta) {
-        Ok(_) => {}
-        Err(_) => return Ok(GameLoopSignal::GameOver),
-    };
+    if game.mode == GameMode::Game && !draw::game::is_window_big_enough(game, window_dim) {
+        game.pause_game();
+        renderer.clear()?;
+    }
+
+    match game.mode {
+        GameMode::Game => renderer.draw_game(game, config, last_delta),
+        GameMode::Pause => renderer.draw_pause_menu(&config.keymap),
+    }
+}
+
+/// Playback speeds, as multiples of the speed the game was played at.
+const REPLAY_SPEEDS: [f64; 5] = [0.5, 1.0, 2.0, 4.0, 8.0];
 
-    if draw::game::is_window_big_enough(&game, window_dim) {
-        draw::game::draw_game_frame(&game, config, window_dim, tick_delta, stdout)?;
-
-        if poll(Duration::from_millis(0))? {
-            match read()? {
-                Event::Key(event) => {
-                    match event.code {
-                        KeyCode::Up | KeyCode::Char('k') | KeyCode::Char('w') => {
-                            game.change_direction(direction::Direction::Up)
-                        }
-                        KeyCode::Down | KeyCode::Char('j') | KeyCode::Char('s') => {
-                            game.change_direction(direction::Direction::Down)
-                        }
-                        KeyCode::Left | KeyCode::Char('h') | KeyCode::Char('a') => {
-                            game.change_direction(direction::Direction::Left)
-                        }
-                        KeyCode::Right | KeyCode::Char('l') | KeyCode::Char('d') => {
-                            game.change_direction(direction::Direction::Right)
-                        }
-                        KeyCode::Esc => {
-                            draw::utils::full_clear(stdout)?;
-                            game.pause_game()
-                        }
-                        _ => {}
-                    };
+/// Plays a recording back until the player quits. The playback can be paused, moved on
+/// one step at a time, sped up or slowed down, and skipped to the end.
+pub fn watch_replay(
+    playback: &mut Playback,
+    config: &Config,
+    events: &mut dyn EventSource,
+    renderer: &mut dyn Renderer,
+) -> std::io::Result<()> {
+    renderer.clear()?;
+
+    let base_interval = playback.game.step_interval();
+    let mut speed = 1;
+    let mut timestep = FixedTimestep::new(base_interval);
+    let mut paused = false;
+
+    let mut last_frame_time = Instant::now();
+    let mut last_draw_time = last_frame_time;
+    let mut needs_redraw = true;
+    loop {
+        if needs_redraw {
+            let now = Instant::now();
+            let delta = now - last_draw_time;
+            renderer.draw_replay(playback, REPLAY_SPEEDS[speed], paused, config, delta)?;
+            last_draw_time = now;
+            needs_redraw = false;
+        }
+
+        let running = !paused && !playback.is_finished();
+        let timeout = running.then(|| timestep.until_next_step());
+        let input = collect_input(events, &config.keymap, timeout)?;
+
+        let now = Instant::now();
+        let delta = now - last_frame_time;
+        last_frame_time = now;
+
+        if input.resized.is_some() {
+            renderer.clear()?;
+            needs_redraw = true;
+        }
+
+        let speed_before = speed;
+        for action in &input.actions {
+            match action {
+                Action::Quit => return Ok(()),
+                Action::Pause => paused = !paused,
+                Action::Right => {
+                    paused = true;
+                    playback.step();
                 }
-                Event::Resize(cols, rows) => {
-                    let new_window_dim = (rows, cols);
-                    if draw::game::is_window_big_enough(&game, new_window_dim) {
-                        draw::utils::full_clear(stdout)?;
+                Action::Up => speed = (speed + 1).min(REPLAY_SPEEDS.len() - 1),
+                Action::Down => speed = speed.saturating_sub(1),
+                Action::Confirm => playback.finish(),
+                _ => continue,
+            }
+            needs_redraw = true;
+        }
+        if speed != speed_before {
+            timestep = FixedTimestep::new(base_interval.div_f64(REPLAY_SPEEDS[speed]));
+        }
+
+        // Time spent paused does not count towards the next move
+        if running && !paused {
+            for _ in 0..timestep.advance(delta) {
+                playback.step();
+                needs_redraw = true;
+            }
+        }
+    }
+}
+
+/// Lets the player type their name for the high score table on the game over screen.
+pub fn ask_for_name(
+    summary: &GameSummary,
+    config: &Config,
+    events: &mut dyn EventSource,
+    renderer: &mut dyn Renderer,
+) -> std::io::Result<String> {
+    let mut name = String::new();
+    renderer.clear()?;
+
+    loop {
+        renderer.draw_game_over(summary, Some(&name), &config.keymap)?;
+
+        for event in events.wait_for_events(None)? {
+            match event {
+                Event::Key(event) if event.kind != KeyEventKind::Release => match event.code {
+                    KeyCode::Enter => return Ok(name),
+                    KeyCode::Backspace => {
+                        name.pop();
                     }
-                }
+                    // Shortcuts such as Ctrl+C do not type anything
+                    KeyCode::Char(_)
+                        if event
+                            .modifiers
+                            .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => {}
+                    KeyCode::Char(c) if name.chars().count() < 16 => name.push(c),
+                    _ => {}
+                },
+                Event::Resize(_, _) => renderer.clear()?,
                 _ => {}
             }
         }
-    } else {
-        game.pause_game();
-        draw::utils::full_clear(stdout)?;
     }
-
-    Ok(GameLoopSignal::Ok)
 }
 
-fn loop_pause_mode(
-    game: &mut Game,
-    stdout: &mut std::io::Stdout,
-) -> std::io::Result<GameLoopSignal> {
-    let window_dim = window::window_dimensions();
+/// Shows the game over screen until the player decides what to do next.
+pub fn show_game_over(
+    summary: &GameSummary,
+    config: &Config,
+    events: &mut dyn EventSource,
+    renderer: &mut dyn Renderer,
+) -> std::io::Result<GameOverChoice> {
+    renderer.clear()?;
 
-    draw_pause_screen(window_dim, stdout)?;
+    loop {
+        renderer.draw_game_over(summary, None, &config.keymap)?;
 
-    if poll(Duration::from_millis(0))? {
-        match read()? {
-            Event::Key(event) => {
-                match event.code {
-                    KeyCode::Char('q') | KeyCode::Char('x') => return Ok(GameLoopSignal::Exit),
+        let input = collect_input(events, &config.keymap, None)?;
+        if input.resized.is_some() {
+            renderer.clear()?;
+        }
+        for action in input.actions {
+            match action {
+                Action::Restart => return Ok(GameOverChoice::Restart),
+                Action::Menu => return Ok(GameOverChoice::Menu),
+                Action::Quit | Action::Pause => return Ok(GameOverChoice::Quit),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Shows the title screen until the player starts a game or quits.
+pub fn show_main_menu(
+    menu: &mut MainMenu,
+    config: &Config,
+    events: &mut dyn EventSource,
+    renderer: &mut dyn Renderer,
+) -> std::io::Result<MenuChoice> {
+    renderer.clear()?;
+
+    loop {
+        renderer.draw_main_menu(menu, &config.keymap)?;
 
-                    KeyCode::Esc => {
-                        draw::utils::full_clear(stdout)?;
-                        game.unpause_game()
+        let input = collect_input(events, &config.keymap, None)?;
+        if input.resized.is_some() {
+            renderer.clear()?;
+        }
+        for action in input.actions {
+            match action {
+                Action::Up => menu.select_previous(),
+                Action::Down => menu.select_next(),
+                Action::Left => menu.change(false),
+                Action::Right => menu.change(true),
+                Action::Confirm => {
+                    if let Some(choice) = menu.activate() {
+                        return Ok(choice);
                     }
-                    _ => {}
-                };
+                }
+                Action::Quit | Action::Pause => return Ok(MenuChoice::Quit),
+                _ => {}
             }
-            Event::Resize(cols, rows) => {
-                let new_window_dim = (rows, cols);
-                draw::utils::full_clear(stdout)?;
-                draw_pause_screen(new_window_dim, stdout)?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Theme;
+    use crate::draw::renderer::MemoryRenderer;
+    use crate::input::ScriptedEvents;
+    use crate::keymap::Keymap;
+    use crate::level::Level;
+    use crate::replay::Replay;
+    use crate::settings::{GameSettings, WallMode};
+    use crossterm::event::{KeyEvent, KeyModifiers};
+
+    fn key(code: KeyCode) -> Event {
+        Event::Key(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    /// A game on an empty 8x16 board with the snake's head in the top left corner,
+    /// moving down.
+    fn new_game() -> Game {
+        let settings = GameSettings::for_test(Level::empty((8, 16)), WallMode::Wrap);
+        let mut game = Game::new(&settings, 0);
+        game.set_input_buffer(3);
+        game
+    }
+
+    fn config() -> Config {
+        Config {
+            show_fps_counter: false,
+            input_buffer: 3,
+            theme: Theme::default(),
+            keymap: Keymap::default(),
+            autopilot: None,
+        }
+    }
+
+    fn next_frame_actions(source: &mut ScriptedEvents) -> Vec<Action> {
+        collect_input(source, &Keymap::default(), None)
+            .unwrap()
+            .actions
+    }
+
+    #[test]
+    fn all_pending_events_are_read_in_one_frame() {
+        let mut source = ScriptedEvents::new(vec![vec![
+            key(KeyCode::Left),
+            Event::Resize(80, 24),
+            key(KeyCode::Char('w')),
+            key(KeyCode::Char('z')),
+        ]]);
+
+        let input = collect_input(&mut source, &Keymap::default(), None).unwrap();
+        assert_eq!(input.actions, vec![Action::Left, Action::Up]);
+        assert_eq!(input.resized, Some((24, 80)));
+
+        assert!(next_frame_actions(&mut source).is_empty());
+    }
+
+    #[test]
+    fn quick_turns_pressed_within_one_frame_are_all_taken() {
+        let mut game = new_game();
+        let mut source =
+            ScriptedEvents::new(vec![vec![key(KeyCode::Left), key(KeyCode::Up)], vec![]]);
+
+        apply_actions(&mut game, &next_frame_actions(&mut source));
+
+        game.step();
+        assert_eq!(game.snake_body()[0], (0, 15));
+        game.step();
+        assert_eq!(game.snake_body()[0], (7, 15));
+    }
+
+    #[test]
+    fn turns_are_ignored_while_paused() {
+        let mut game = new_game();
+        let mut source = ScriptedEvents::new(vec![vec![
+            key(KeyCode::Esc),
+            key(KeyCode::Left),
+            key(KeyCode::Esc),
+        ]]);
+
+        apply_actions(&mut game, &next_frame_actions(&mut source));
+
+        assert!(game.mode == GameMode::Game);
+        game.step();
+        assert_eq!(game.snake_body()[0], (1, 0));
+    }
+
+    #[test]
+    fn pause_menu_is_shown_until_the_player_quits() {
+        let mut game = new_game();
+        let mut source =
+            ScriptedEvents::new(vec![vec![key(KeyCode::Esc)], vec![key(KeyCode::Char('q'))]]);
+        let mut renderer = MemoryRenderer::new((24, 80));
+
+        let signal = start_game(&mut game, None, &config(), &mut source, &mut renderer).unwrap();
+
+        assert!(matches!(signal, GameLoopSignal::Exit));
+        assert_eq!(renderer.frames_presented, 2);
+        let lines = renderer.frame().unwrap().lines();
+        assert!(lines.iter().any(|line| line.contains("PAUSED")));
+    }
+
+    fn summary() -> GameSummary {
+        GameSummary {
+            reason: GameEnd::GameOver(GameOver::WallCollision),
+            score: 12,
+            length: 7,
+            rival_score: None,
+            time_played: Duration::from_secs(30),
+            best: None,
+        }
+    }
+
+    #[test]
+    fn main_menu_starts_the_highlighted_item() {
+        let mut menu = MainMenu::new(5, 16, 8, WallMode::Wrap, false, true);
+        let mut source =
+            ScriptedEvents::new(vec![vec![key(KeyCode::Down)], vec![key(KeyCode::Enter)]]);
+        let mut renderer = MemoryRenderer::new((24, 80));
+
+        let choice = show_main_menu(&mut menu, &config(), &mut source, &mut renderer).unwrap();
+
+        assert!(matches!(choice, MenuChoice::Campaign));
+        assert_eq!(renderer.frames_presented, 2);
+    }
+
+    #[test]
+    fn game_over_screen_waits_for_a_choice() {
+        let mut source = ScriptedEvents::new(vec![
+            vec![key(KeyCode::Left), Event::Resize(100, 30)],
+            vec![key(KeyCode::Char('m'))],
+        ]);
+        let mut renderer = MemoryRenderer::new((24, 80));
+
+        let choice = show_game_over(&summary(), &config(), &mut source, &mut renderer).unwrap();
+
+        assert!(matches!(choice, GameOverChoice::Menu));
+        let lines = renderer.frame().unwrap().lines();
+        assert!(lines.iter().any(|line| line.contains("GAME OVER")));
+    }
+
+    #[test]
+    fn name_is_typed_without_shortcuts() {
+        let ctrl_c = Event::Key(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL));
+        let mut source = ScriptedEvents::new(vec![
+            vec![key(KeyCode::Char('A')), key(KeyCode::Char('d')), ctrl_c],
+            vec![key(KeyCode::Char('x')), key(KeyCode::Backspace)],
+            vec![key(KeyCode::Char('a')), key(KeyCode::Enter)],
+        ]);
+        let mut renderer = MemoryRenderer::new((24, 80));
+
+        let name = ask_for_name(&summary(), &config(), &mut source, &mut renderer).unwrap();
+
+        assert_eq!(name, "Ada");
+    }
+
+    /// Turns left once, then gives up.
+    struct Quitter {
+        moves: u32,
+    }
+
+    impl Controller for Quitter {
+        fn next_direction(&mut self, _game: &Game) -> Result<direction::Direction, Forfeit> {
+            self.moves += 1;
+            match self.moves {
+                1 => Ok(direction::Direction::Left),
+                _ => Err(Forfeit::Gone),
             }
-            _ => {}
         }
     }
 
-    Ok(GameLoopSignal::Ok)
+    #[test]
+    fn controller_steers_before_every_step_until_it_forfeits() {
+        let mut game = new_game();
+        let mut controller = Quitter { moves: 0 };
+
+        let signal = play_steps(&mut game, 3, Some(&mut controller));
+
+        assert!(matches!(
+            signal,
+            GameLoopSignal::GameOver(GameEnd::Forfeit(Forfeit::Gone))
+        ));
+        assert_eq!(game.steps, 1);
+        assert_eq!(game.snake_body()[0], (0, 15));
+    }
+
+    fn recorded_playback() -> Playback {
+        let settings = GameSettings::for_test(Level::empty((8, 16)), WallMode::Wrap);
+        let mut game = Game::new(&settings, 3);
+        for _ in 0..20 {
+            game.step();
+        }
+        Playback::new(Replay::new(&settings, &game))
+    }
+
+    fn watch(playback: &mut Playback, events: Vec<Vec<Event>>) {
+        let mut source = ScriptedEvents::new(events);
+        let mut renderer = MemoryRenderer::new((24, 80));
+        watch_replay(playback, &config(), &mut source, &mut renderer).unwrap();
+    }
+
+    #[test]
+    fn replay_moves_one_step_at_a_time() {
+        let mut playback = recorded_playback();
+        watch(
+            &mut playback,
+            vec![
+                vec![key(KeyCode::Right)],
+                vec![key(KeyCode::Right)],
+                vec![key(KeyCode::Char('q'))],
+            ],
+        );
+
+        assert_eq!(playback.game.steps, 2);
+    }
+
+    #[test]
+    fn replay_skips_to_the_end() {
+        let mut playback = recorded_playback();
+        watch(
+            &mut playback,
+            vec![vec![key(KeyCode::Enter)], vec![key(KeyCode::Char('q'))]],
+        );
+
+        assert!(playback.is_finished());
+        assert!(playback.verify().is_ok());
+    }
+
+    #[test]
+    fn pausing_and_quitting_in_one_frame_exits() {
+        let mut game = new_game();
+        let mut source =
+            ScriptedEvents::new(vec![vec![key(KeyCode::Esc), key(KeyCode::Char('q'))]]);
+
+        let signal = apply_actions(&mut game, &next_frame_actions(&mut source));
+
+        assert!(matches!(signal, GameLoopSignal::Exit));
+    }
 }
@@ -0,0 +1,84 @@
+use crossterm::event::{poll, read, Event, KeyEventKind};
+use std::time::Duration;
+
+use crate::keymap::{Action, Keymap};
+use crate::window::WindowDimensions;
+
+/// Where the game loop takes terminal events from.
+pub trait EventSource {
+    /// Waits up to `timeout` (forever if `None`) for the first event, then returns it
+    /// together with every other event that is already waiting.
+    fn wait_for_events(&mut self, timeout: Option<Duration>) -> std::io::Result<Vec<Event>>;
+}
+
+/// Events from the real terminal.
+pub struct TerminalEvents;
+
+impl EventSource for TerminalEvents {
+    fn wait_for_events(&mut self, timeout: Option<Duration>) -> std::io::Result<Vec<Event>> {
+        let mut events = vec![];
+        match timeout {
+            Some(timeout) if !poll(timeout)? => return Ok(events),
+            Some(_) => {}
+            None => events.push(read()?),
+        }
+
+        while poll(Duration::ZERO)? {
+            events.push(read()?);
+        }
+        Ok(events)
+    }
+}
+
+/// Everything the player did since the previous frame.
+#[derive(Default)]
+pub struct FrameInput {
+    /// Actions in the order the keys were pressed
+    pub actions: Vec<Action>,
+    /// The latest window size, if the window was resized
+    pub resized: Option<WindowDimensions>,
+}
+
+/// Waits for events like [`EventSource::wait_for_events`] and turns them into actions.
+pub fn collect_input(
+    source: &mut dyn EventSource,
+    keymap: &Keymap,
+    timeout: Option<Duration>,
+) -> std::io::Result<FrameInput> {
+    let mut input = FrameInput::default();
+
+    for event in source.wait_for_events(timeout)? {
+        match event {
+            Event::Key(event) if event.kind != KeyEventKind::Release => {
+                input.actions.extend(keymap.action(&event))
+            }
+            Event::Resize(cols, rows) => input.resized = Some((rows, cols)),
+            _ => {}
+        }
+    }
+
+    Ok(input)
+}
+
+/// Plays back a fixed list of events in place of a terminal, one batch per call and
+/// without waiting.
+#[cfg(test)]
+pub struct ScriptedEvents {
+    frames: std::collections::VecDeque<Vec<Event>>,
+}
+
+#[cfg(test)]
+impl ScriptedEvents {
+    pub fn new(frames: Vec<Vec<Event>>) -> ScriptedEvents {
+        ScriptedEvents {
+            frames: frames.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+impl EventSource for ScriptedEvents {
+    fn wait_for_events(&mut self, _timeout: Option<Duration>) -> std::io::Result<Vec<Event>> {
+        Ok(self.frames.pop_front().unwrap_or_default())
+    }
+}
@@ -0,0 +1,281 @@
+//! Mapping from key presses to game actions.
+//!
+//! Keys are named in the config file either by the character they type (`"k"`, `"+"`) or
+//! by one of `up`, `down`, `left`, `right`, `esc`, `enter`, `space`, `tab`, `backspace`,
+//! `home`, `end`, `pageup`, `pagedown`, `f1`-`f12`. Binding an action in the config file
+//! replaces all of its default keys. A key that is bound to more than one action, by
+//! default or in the config file, is an error.
+//!
+//! The `player2-*` actions steer the second snake in versus games. Everywhere else they
+//! do the same as the first player's directions, so either set of keys works.
+use crossterm::event::{KeyCode, KeyEvent};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Action {
+    Up,
+    Down,
+    Left,
+    Right,
+    Player2Up,
+    Player2Down,
+    Player2Left,
+    Player2Right,
+    /// Pauses and resumes the game, leaves the other screens
+    Pause,
+    Quit,
+    Restart,
+    Menu,
+    Confirm,
+}
+
+const DEFAULT_BINDINGS: [(Action, &[KeyCode]); 13] = [
+    (Action::Up, &[KeyCode::Up, KeyCode::Char('k')]),
+    (Action::Down, &[KeyCode::Down, KeyCode::Char('j')]),
+    (Action::Left, &[KeyCode::Left, KeyCode::Char('h')]),
+    (Action::Right, &[KeyCode::Right, KeyCode::Char('l')]),
+    (Action::Player2Up, &[KeyCode::Char('w')]),
+    (Action::Player2Down, &[KeyCode::Char('s')]),
+    (Action::Player2Left, &[KeyCode::Char('a')]),
+    (Action::Player2Right, &[KeyCode::Char('d')]),
+    (Action::Pause, &[KeyCode::Esc]),
+    (Action::Quit, &[KeyCode::Char('q'), KeyCode::Char('x')]),
+    (Action::Restart, &[KeyCode::Char('r')]),
+    (Action::Menu, &[KeyCode::Char('m')]),
+    (Action::Confirm, &[KeyCode::Enter]),
+];
+
+#[derive(Clone)]
+pub struct Keymap {
+    bindings: HashMap<KeyCode, Action>,
+    /// Keeps the second player's keys apart instead of merging them into the first's
+    versus: bool,
+}
+
+impl Default for Keymap {
+    fn default() -> Keymap {
+        let bindings = DEFAULT_BINDINGS
+            .iter()
+            .flat_map(|(action, keys)| keys.iter().map(move |key| (*key, *action)))
+            .collect();
+
+        Keymap {
+            bindings,
+            versus: false,
+        }
+    }
+}
+
+impl Keymap {
+    /// Builds the keymap from the `[keys]` section of the config file, keeping the default
+    /// keys of every action that is not mentioned there. The actions are bound in the order
+    /// they are declared in, so a conflict is always reported the same way.
+    pub fn with_overrides(overrides: &BTreeMap<Action, Vec<String>>) -> Result<Keymap, String> {
+        let mut keymap = Keymap::default();
+        keymap
+            .bindings
+            .retain(|_, action| !overrides.contains_key(action));
+
+        for (action, names) in overrides {
+            for name in names {
+                let key = parse_key(name).ok_or_else(|| format!("unknown key '{}'", name))?;
+                match keymap.bindings.insert(key, *action) {
+                    Some(other) if other != *action => {
+                        return Err(format!(
+                            "key '{}' is bound to both {:?} and {:?}",
+                            name, other, action
+                        ))
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(keymap)
+    }
+
+    /// The same keymap for a versus game, where the `player2-*` actions steer the second
+    /// snake.
+    pub fn versus(&self) -> Keymap {
+        Keymap {
+            bindings: self.bindings.clone(),
+            versus: true,
+        }
+    }
+
+    pub fn action(&self, event: &KeyEvent) -> Option<Action> {
+        let code = match event.code {
+            KeyCode::Char(c) => KeyCode::Char(c.to_ascii_lowercase()),
+            code => code,
+        };
+        let action = self.bindings.get(&code).copied()?;
+
+        Some(match action {
+            _ if self.versus => action,
+            Action::Player2Up => Action::Up,
+            Action::Player2Down => Action::Down,
+            Action::Player2Left => Action::Left,
+            Action::Player2Right => Action::Right,
+            action => action,
+        })
+    }
+
+    /// Name of a key bound to `action`, for the help texts on screen.
+    pub fn key_name(&self, action: Action) -> String {
+        let mut keys: Vec<&KeyCode> = self
+            .bindings
+            .iter()
+            .filter(|(_, a)| **a == action)
+            .map(|(key, _)| key)
+            .collect();
+        // Prefer the default keys, in their default order
+        let default_rank = |key: &KeyCode| {
+            DEFAULT_BINDINGS
+                .iter()
+                .flat_map(|(_, keys)| keys.iter())
+                .position(|k| k == key)
+                .unwrap_or(usize::MAX)
+        };
+        keys.sort_by_key(|key| (default_rank(key), format!("{:?}", key)));
+
+        keys.first()
+            .map_or(String::from("?"), |key| key_to_name(key))
+    }
+}
+
+fn parse_key(name: &str) -> Option<KeyCode> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(KeyCode::Char(c.to_ascii_lowercase()));
+    }
+
+    let code = match name.to_lowercase().as_str() {
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "esc" | "escape" => KeyCode::Esc,
+        "enter" | "return" => KeyCode::Enter,
+        "space" => KeyCode::Char(' '),
+        "tab" => KeyCode::Tab,
+        "backspace" => KeyCode::Backspace,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        other => {
+            let number: u8 = other.strip_prefix('f')?.parse().ok()?;
+            if !(1..=12).contains(&number) {
+                return None;
+            }
+            KeyCode::F(number)
+        }
+    };
+
+    Some(code)
+}
+
+fn key_to_name(key: &KeyCode) -> String {
+    match key {
+        KeyCode::Char(' ') => String::from("space"),
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::F(n) => format!("f{}", n),
+        KeyCode::Esc => String::from("esc"),
+        KeyCode::PageUp => String::from("pageup"),
+        KeyCode::PageDown => String::from("pagedown"),
+        other => format!("{:?}", other).to_lowercase(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyModifiers;
+
+    fn overrides(bindings: &[(Action, &[&str])]) -> BTreeMap<Action, Vec<String>> {
+        bindings
+            .iter()
+            .map(|(action, names)| (*action, names.iter().map(|n| n.to_string()).collect()))
+            .collect()
+    }
+
+    fn press(keymap: &Keymap, code: KeyCode) -> Option<Action> {
+        keymap.action(&KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    #[test]
+    fn keys_are_parsed_by_name_or_character() {
+        assert_eq!(parse_key("k"), Some(KeyCode::Char('k')));
+        assert_eq!(parse_key("+"), Some(KeyCode::Char('+')));
+        assert_eq!(parse_key("space"), Some(KeyCode::Char(' ')));
+        assert_eq!(parse_key("escape"), Some(KeyCode::Esc));
+        assert_eq!(parse_key("PageDown"), Some(KeyCode::PageDown));
+        assert_eq!(parse_key("f12"), Some(KeyCode::F(12)));
+        assert_eq!(parse_key("f13"), None);
+        assert_eq!(parse_key("f0"), None);
+        assert_eq!(parse_key("jump"), None);
+        assert_eq!(parse_key(""), None);
+    }
+
+    #[test]
+    fn letters_match_either_case() {
+        assert_eq!(parse_key("K"), Some(KeyCode::Char('k')));
+
+        let keymap = Keymap::with_overrides(&overrides(&[(Action::Up, &["I"])])).unwrap();
+        assert_eq!(press(&keymap, KeyCode::Char('i')), Some(Action::Up));
+        assert_eq!(press(&keymap, KeyCode::Char('I')), Some(Action::Up));
+    }
+
+    #[test]
+    fn overrides_replace_the_default_keys_of_an_action() {
+        let keymap = Keymap::with_overrides(&overrides(&[(Action::Pause, &["p"])])).unwrap();
+
+        assert_eq!(press(&keymap, KeyCode::Char('p')), Some(Action::Pause));
+        assert_eq!(press(&keymap, KeyCode::Esc), None);
+        assert_eq!(press(&keymap, KeyCode::Char('q')), Some(Action::Quit));
+        assert_eq!(keymap.key_name(Action::Pause), "p");
+    }
+
+    #[test]
+    fn a_key_bound_to_two_actions_is_an_error() {
+        let error = Keymap::with_overrides(&overrides(&[
+            (Action::Restart, &["n"]),
+            (Action::Menu, &["n"]),
+        ]))
+        .err()
+        .unwrap();
+
+        assert_eq!(error, "key 'n' is bound to both Restart and Menu");
+        assert!(Keymap::with_overrides(&overrides(&[(Action::Up, &["i", "I"])])).is_ok());
+    }
+
+    #[test]
+    fn default_keys_of_other_actions_can_not_be_taken() {
+        let error = |bindings: &[(Action, &[&str])]| {
+            Keymap::with_overrides(&overrides(bindings)).err().unwrap()
+        };
+
+        assert_eq!(
+            error(&[(Action::Pause, &["q"])]),
+            "key 'q' is bound to both Quit and Pause"
+        );
+        assert_eq!(
+            error(&[(Action::Up, &["up", "k", "w"])]),
+            "key 'w' is bound to both Player2Up and Up"
+        );
+        assert!(Keymap::with_overrides(&overrides(&[
+            (Action::Up, &["up", "k", "w"]),
+            (Action::Player2Up, &["i"]),
+        ]))
+        .is_ok());
+    }
+
+    #[test]
+    fn unknown_keys_are_an_error() {
+        let error = Keymap::with_overrides(&overrides(&[(Action::Up, &["jump"])]));
+
+        assert_eq!(error.err().unwrap(), "unknown key 'jump'");
+    }
+}
@@ -0,0 +1,286 @@
+//! Board layouts loaded from plain-text maps.
+//!
+//! Every line of a map is one row of the board and every character one field:
+//!
+//! - `#` - wall
+//! - `.` or ` ` - floor
+//! - `^`, `>`, `v`, `<` - the snake's head and its starting direction (`S` is the same as `>`)
+//!
+//! The map must contain exactly one start, with two floor fields behind it for the rest
+//! of the snake, and at least one more floor field for the food. Lines starting with `;`
+//! are comments. All rows must be equally long.
+use std::collections::HashSet;
+use std::fmt;
+use std::path::Path;
+
+use crate::direction::Direction;
+
+#[derive(Clone)]
+pub struct Level {
+    /// Identifies custom layouts, `None` for an empty board
+    pub name: Option<String>,
+    pub dimensions: (u16, u16),
+    pub obstacles: HashSet<(u16, u16)>,
+    pub snake: Vec<(u16, u16)>,
+    pub direction: Direction,
+}
+
+#[derive(Debug)]
+pub enum LevelError {
+    Io(std::io::Error),
+    Parse { line: usize, message: String },
+}
+
+impl fmt::Display for LevelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LevelError::Io(e) => write!(f, "{}", e),
+            LevelError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl Level {
+    /// An empty rectangular board with the snake in the top-left corner.
+    pub fn empty(dimensions: (u16, u16)) -> Level {
+        Level {
+            name: None,
+            dimensions,
+            obstacles: HashSet::new(),
+            snake: vec![(0, 0), (0, 1), (0, 2)],
+            direction: Direction::Down,
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Level, LevelError> {
+        let text = std::fs::read_to_string(path).map_err(LevelError::Io)?;
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        Level::parse(&name, &text)
+    }
+
+    pub fn parse(name: &str, text: &str) -> Result<Level, LevelError> {
+        let mut obstacles = HashSet::new();
+        let mut start: Option<((u16, u16), Direction)> = None;
+        let mut width: Option<usize> = None;
+        let mut row: u16 = 0;
+        let mut last_line = 0;
+
+        for (i, line) in text.lines().enumerate() {
+            let line_no = i + 1;
+            let line = line.trim_end_matches('\r');
+            if line.starts_with(';') || line.is_empty() {
+                continue;
+            }
+            last_line = line_no;
+
+            let fields: Vec<char> = line.chars().collect();
+            match width {
+                None => width = Some(fields.len()),
+                Some(w) if w != fields.len() => {
+                    return Err(parse_error(
+                        line_no,
+                        format!("row is {} fields wide, expected {}", fields.len(), w),
+                    ))
+                }
+                _ => {}
+            }
+            if row >= 255 || fields.len() > 255 {
+                return Err(parse_error(line_no, "map is larger than 255x255".into()));
+            }
+
+            for (col, field) in fields.iter().enumerate() {
+                let pos = (row, col as u16);
+                let direction = match field {
+                    '#' => {
+                        obstacles.insert(pos);
+                        continue;
+                    }
+                    '.' | ' ' => continue,
+                    '^' => Direction::Up,
+                    '>' | 'S' => Direction::Right,
+                    'v' => Direction::Down,
+                    '<' => Direction::Left,
+                    other => {
+                        return Err(parse_error(line_no, format!("unknown field '{}'", other)))
+                    }
+                };
+                if start.is_some() {
+                    return Err(parse_error(line_no, "more than one start".into()));
+                }
+                start = Some((pos, direction));
+            }
+            row += 1;
+        }
+
+        let dimensions = (row, width.unwrap_or(0) as u16);
+        if dimensions.0 < 3 || dimensions.1 < 3 {
+            return Err(parse_error(last_line, "map must be at least 3x3".into()));
+        }
+        let Some((head, direction)) = start else {
+            return Err(parse_error(last_line, "map has no start".into()));
+        };
+
+        let snake = (0..3)
+            .map(|i| behind(head, direction, i))
+            .collect::<Option<Vec<_>>>()
+            .filter(|body| {
+                body.iter()
+                    .all(|e| e.0 < dimensions.0 && e.1 < dimensions.1 && !obstacles.contains(e))
+            })
+            .ok_or_else(|| {
+                parse_error(
+                    last_line,
+                    "start needs two floor fields behind it for the snake".into(),
+                )
+            })?;
+
+        let fields = dimensions.0 as usize * dimensions.1 as usize;
+        if fields <= obstacles.len() + snake.len() {
+            return Err(parse_error(last_line, "no room for food".into()));
+        }
+
+        Ok(Level {
+            name: Some(name.to_string()),
+            dimensions,
+            obstacles,
+            snake,
+            direction,
+        })
+    }
+
+    /// Start of the second snake in a versus game: the first snake's start turned half way
+    /// around the centre of the board. `None` if that runs into a wall or the first snake,
+    /// or leaves no room for food.
+    pub fn rival_start(&self) -> Option<(Vec<(u16, u16)>, Direction)> {
+        let (rows, cols) = self.dimensions;
+        let snake: Vec<(u16, u16)> = self
+            .snake
+            .iter()
+            .map(|(row, col)| (rows - 1 - row, cols - 1 - col))
+            .collect();
+
+        let is_free = snake
+            .iter()
+            .all(|pos| !self.obstacles.contains(pos) && !self.snake.contains(pos));
+        let fields = rows as usize * cols as usize;
+        let has_room = fields > self.obstacles.len() + self.snake.len() + snake.len();
+        (is_free && has_room).then(|| (snake, self.direction.opposite()))
+    }
+
+    /// The map in the format read by [`Level::parse`].
+    pub fn to_map(&self) -> String {
+        let head = self.snake[0];
+        let mut map = String::new();
+        for row in 0..self.dimensions.0 {
+            for col in 0..self.dimensions.1 {
+                let field = match (row, col) {
+                    pos if pos == head => match self.direction {
+                        Direction::Up => '^',
+                        Direction::Right => '>',
+                        Direction::Down => 'v',
+                        Direction::Left => '<',
+                    },
+                    pos if self.obstacles.contains(&pos) => '#',
+                    _ => '.',
+                };
+                map.push(field);
+            }
+            map.push('\n');
+        }
+        map
+    }
+}
+
+fn parse_error(line: usize, message: String) -> LevelError {
+    LevelError::Parse { line, message }
+}
+
+/// The field `distance` steps behind `head` when moving in `direction`.
+fn behind(head: (u16, u16), direction: Direction, distance: u16) -> Option<(u16, u16)> {
+    match direction {
+        Direction::Up => Some((head.0.checked_add(distance)?, head.1)),
+        Direction::Down => Some((head.0.checked_sub(distance)?, head.1)),
+        Direction::Left => Some((head.0, head.1.checked_add(distance)?)),
+        Direction::Right => Some((head.0, head.1.checked_sub(distance)?)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn map_is_read_into_walls_and_a_start() {
+        let level = Level::parse("box", "#####\n#...#\n#..>#\n#####\n").unwrap();
+
+        assert_eq!(level.name.as_deref(), Some("box"));
+        assert_eq!(level.dimensions, (4, 5));
+        assert_eq!(level.obstacles.len(), 14);
+        assert!(level.obstacles.contains(&(0, 0)));
+        assert!(!level.obstacles.contains(&(1, 1)));
+        assert_eq!(level.snake, vec![(2, 3), (2, 2), (2, 1)]);
+        assert_eq!(level.direction, Direction::Right);
+    }
+
+    #[test]
+    fn start_marker_sets_the_direction() {
+        let start = |map: &str| {
+            let level = Level::parse("start", map).unwrap();
+            (level.snake, level.direction)
+        };
+
+        assert_eq!(
+            start("...\n...\n.^.\n...\n...\n"),
+            (vec![(2, 1), (3, 1), (4, 1)], Direction::Up)
+        );
+        assert_eq!(
+            start("...\n...\n.v.\n...\n...\n"),
+            (vec![(2, 1), (1, 1), (0, 1)], Direction::Down)
+        );
+        assert_eq!(
+            start(".....\n.<...\n.....\n"),
+            (vec![(1, 1), (1, 2), (1, 3)], Direction::Left)
+        );
+        assert_eq!(
+            start(".....\n...S.\n.....\n"),
+            (vec![(1, 3), (1, 2), (1, 1)], Direction::Right)
+        );
+    }
+
+    #[test]
+    fn comment_lines_are_skipped() {
+        let level = Level::parse("commented", "; a small box\n....\n;\n..>.\n....\n").unwrap();
+
+        assert_eq!(level.dimensions, (3, 4));
+        assert_eq!(level.snake[0], (1, 2));
+    }
+
+    #[test]
+    fn rows_must_be_equally_long() {
+        let error = Level::parse("ragged", "....\n..>.\n...\n").err().unwrap();
+
+        assert_eq!(
+            error.to_string(),
+            "line 3: row is 3 fields wide, expected 4"
+        );
+    }
+
+    #[test]
+    fn map_without_room_for_food_is_rejected() {
+        let error = Level::parse("full", "###\n..>\n###\n").err().unwrap();
+
+        assert_eq!(error.to_string(), "line 3: no room for food");
+        assert!(Level::parse("roomy", "####\n..>.\n####\n").is_ok());
+    }
+
+    #[test]
+    fn rival_needs_room_for_food() {
+        let level = Level::parse("corridor", "######\n..>...\n######\n").unwrap();
+
+        assert!(level.rival_start().is_none());
+    }
+}
@@ -1,23 +1,180 @@
-use clap::Parser;
+use clap::parser::ValueSource;
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
 
 use std::io::stdout;
+use std::path::{Path, PathBuf};
+use std::process::exit;
 
+mod autopilot;
+mod bench;
 mod board;
+mod bot;
+mod campaign;
 mod config;
 mod consts;
 mod direction;
 mod draw;
 mod game;
 mod game_loop;
+mod input;
+mod keymap;
+mod level;
+mod menu;
+mod net;
+mod replay;
+mod scores;
+mod settings;
 mod snake;
+mod storage;
 mod terminal;
+mod timestep;
 mod utils;
 mod window;
 
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
 struct Args {
-    /// Game speed
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Game speed [default: 5]
+    #[arg(short, long, value_parser = clap::value_parser!(u16).range(1..=9))]
+    difficulty: Option<u16>,
+
+    /// Board width [default: 16]
+    #[arg(long, value_parser = clap::value_parser!(u16).range(3..256))]
+    width: Option<u16>,
+
+    /// Board height [default: 8]
+    #[arg(long, value_parser = clap::value_parser!(u16).range(3..256))]
+    height: Option<u16>,
+
+    /// Play on a board loaded from a text map instead of an empty rectangle
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["width", "height"])]
+    level: Option<PathBuf>,
+
+    /// What happens when the snake reaches the edge of the board [default: wrap]
+    #[arg(long, value_enum)]
+    walls: Option<settings::WallMode>,
+
+    /// Play the built-in campaign, continuing from the highest unlocked level
+    #[arg(long, conflicts_with_all = ["level", "width", "height", "walls", "difficulty"])]
+    campaign: bool,
+
+    /// Campaign level to start from, up to the highest unlocked one
+    #[arg(long, value_name = "N", requires = "campaign", value_parser = clap::value_parser!(u16).range(1..))]
+    campaign_level: Option<u16>,
+
+    /// Two players on one board, the second one steering with WASD
+    #[arg(long, conflicts_with_all = ["campaign", "autopilot", "bot_cmd", "record"])]
+    versus: bool,
+
+    /// Seed for food and fruit spawns; the same seed replays the same game
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// How many turns can be queued ahead of the snake's moves [default: 3]
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u8).range(1..=8))]
+    input_buffer: Option<u8>,
+
+    /// Show FPS counter
+    #[arg(long, default_value_t = false)]
+    show_fps: bool,
+
+    /// Let the computer play, with the given strategy
+    #[arg(long, value_enum, value_name = "STRATEGY", num_args = 0..=1, default_missing_value = "greedy")]
+    autopilot: Option<autopilot::Strategy>,
+
+    /// Let a program steer the snake, talking JSON lines over its stdin and stdout
+    #[arg(long, value_name = "COMMAND", conflicts_with = "autopilot")]
+    bot_cmd: Option<String>,
+
+    /// Milliseconds the bot has for every move before it forfeits the game [default: 200]
+    #[arg(long, value_name = "MS", requires = "bot_cmd", value_parser = clap::value_parser!(u64).range(1..))]
+    bot_timeout: Option<u64>,
+
+    /// Save a recording of the last game played to this file
+    #[arg(long, value_name = "PATH")]
+    record: Option<PathBuf>,
+
+    /// Play back a recording made with --record and check its final score
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with_all = ["level", "width", "height", "walls", "difficulty", "campaign", "seed", "record", "versus"]
+    )]
+    replay: Option<PathBuf>,
+
+    /// Read settings from this file instead of the default config file
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print the high score table
+    Scores,
+    /// Play games with an autopilot strategy without a terminal and print statistics
+    Bench {
+        /// Strategy that plays the games
+        #[arg(long, value_enum, default_value_t = autopilot::Strategy::Greedy)]
+        strategy: autopilot::Strategy,
+
+        /// Number of games, played with consecutive seeds
+        #[arg(short = 'n', long, default_value_t = 100, value_parser = clap::value_parser!(u64).range(1..))]
+        games: u64,
+
+        /// Seed of the first game
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+
+        #[command(flatten)]
+        board: BoardArgs,
+
+        /// Number of games played at the same time [default: number of CPUs]
+        #[arg(short, long, value_parser = clap::value_parser!(u16).range(1..))]
+        jobs: Option<u16>,
+
+        /// How the results are printed
+        #[arg(long, value_enum, default_value_t = bench::Format::Table)]
+        format: bench::Format,
+    },
+    /// Host versus games that players join over the network
+    Serve {
+        /// Port to listen on, on every network interface
+        #[arg(long, default_value_t = 7878)]
+        port: u16,
+
+        #[command(flatten)]
+        board: BoardArgs,
+
+        /// How many turns can be queued ahead of the snakes' moves
+        #[arg(long, value_name = "N", default_value_t = 3, value_parser = clap::value_parser!(u8).range(1..=8))]
+        input_buffer: u8,
+
+        /// Seconds a game waits for a player who lost their connection before they lose it
+        #[arg(long, value_name = "SECONDS", default_value_t = 30)]
+        reconnect_timeout: u64,
+    },
+    /// Play or watch a versus game hosted with `serve`
+    Join {
+        /// Address of the server, as host:port
+        address: String,
+
+        /// Name shown to the other players [default: your user name]
+        #[arg(long)]
+        name: Option<String>,
+
+        /// Watch the game instead of taking a seat
+        #[arg(long)]
+        spectate: bool,
+    },
+}
+
+/// The board and speed of games that are not set up on the title screen.
+#[derive(clap::Args)]
+struct BoardArgs {
+    /// Game speed, which sets the points per food
     #[arg(short, long, default_value_t = 5, value_parser = clap::value_parser!(u16).range(1..=9))]
     difficulty: u16,
 
@@ -29,42 +186,457 @@ struct Args {
     #[arg(long, default_value_t = 8, value_parser = clap::value_parser!(u16).range(3..256))]
     height: u16,
 
-    /// Show FPS counter
-    #[arg(long, default_value_t = false)]
-    show_fps: bool,
+    /// Play on a board loaded from a text map instead of an empty rectangle
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["width", "height"])]
+    level: Option<PathBuf>,
+
+    /// What happens when the snake reaches the edge of the board
+    #[arg(long, value_enum, default_value_t = settings::WallMode::Wrap)]
+    walls: settings::WallMode,
+}
+
+impl BoardArgs {
+    fn settings(&self, versus: bool) -> settings::GameSettings {
+        settings::GameSettings {
+            level: match &self.level {
+                Some(path) => load_level(path),
+                None => level::Level::empty((self.height, self.width)),
+            },
+            difficulty: self.difficulty,
+            walls: self.walls,
+            goal: None,
+            versus,
+        }
+    }
 }
 
 fn main() {
-    let args = Args::parse();
+    let matches = Args::command().get_matches();
+    let args = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+
+    match args.command {
+        Some(Command::Scores) => {
+            print_scores();
+            return;
+        }
+        Some(Command::Bench {
+            strategy,
+            games,
+            seed,
+            board,
+            jobs,
+            format,
+        }) => {
+            let settings = board.settings(false);
+            let jobs = jobs.map_or_else(
+                || std::thread::available_parallelism().map_or(1, |n| n.get()),
+                usize::from,
+            );
+            run_bench(&settings, strategy, seed, games, jobs, format);
+            return;
+        }
+        Some(Command::Serve {
+            port,
+            board,
+            input_buffer,
+            reconnect_timeout,
+        }) => {
+            let settings = board.settings(true);
+            if settings.level.rival_start().is_none() {
+                eprintln!("The level has no room for a second snake opposite the first one");
+                exit(1);
+            }
+            serve(
+                net::server::ServerOptions {
+                    settings,
+                    input_buffer: input_buffer as usize,
+                    reconnect_timeout: std::time::Duration::from_secs(reconnect_timeout),
+                    next_game_delay: std::time::Duration::from_secs(5),
+                    hello_timeout: std::time::Duration::from_secs(10),
+                },
+                port,
+            );
+            return;
+        }
+        Some(Command::Join { .. }) | None => {}
+    }
+
+    let config_file = match config::ConfigFile::load(args.config.as_deref()) {
+        Ok(config_file) => config_file,
+        Err(e) => {
+            eprintln!("Cannot load config file {}", e);
+            exit(1);
+        }
+    };
 
     let config = config::Config {
-        show_fps_counter: args.show_fps,
+        show_fps_counter: args.show_fps || config_file.show_fps.unwrap_or(false),
+        input_buffer: args.input_buffer.or(config_file.input_buffer).unwrap_or(3) as usize,
+        theme: config_file.colors.theme(),
+        // Already validated when the config file was loaded
+        keymap: keymap::Keymap::with_overrides(&config_file.keys).unwrap(),
+        autopilot: match (args.autopilot, &args.bot_cmd) {
+            (Some(strategy), _) => Some(autopilot::Autopilot::Strategy(strategy)),
+            (None, Some(command)) => Some(autopilot::Autopilot::Bot(bot::BotCommand {
+                command: command.clone(),
+                timeout: std::time::Duration::from_millis(args.bot_timeout.unwrap_or(200)),
+            })),
+            (None, None) => None,
+        },
+    };
+    if let Some(path) = &args.replay {
+        watch_replay(path, &config);
+        return;
+    }
+    if let Some(Command::Join {
+        address,
+        name,
+        spectate,
+    }) = args.command
+    {
+        let name = name
+            .or_else(|| std::env::var("USER").ok())
+            .unwrap_or_else(|| String::from("player"));
+        join(
+            &net::client::JoinOptions {
+                address,
+                name,
+                spectate,
+            },
+            &config,
+        );
+        return;
+    }
+
+    let mut campaign = if args.campaign {
+        let unlocked = campaign::load_unlocked();
+        let start = args.campaign_level.map_or(unlocked, usize::from);
+        if start > unlocked {
+            eprintln!(
+                "Campaign level {} is locked, the highest unlocked level is {}",
+                start, unlocked
+            );
+            exit(1);
+        }
+        Some(campaign::Campaign::new(start - 1))
+    } else {
+        None
+    };
+
+    let level = match &args.level {
+        Some(path) => load_level(path),
+        None => level::Level::empty((
+            args.height.or(config_file.height).unwrap_or(8),
+            args.width.or(config_file.width).unwrap_or(16),
+        )),
     };
-    let difficulty = args.difficulty;
-    let mut game = game::Game::new((args.height, args.width), difficulty);
+
+    let mut settings = settings::GameSettings {
+        level,
+        difficulty: args.difficulty.or(config_file.difficulty).unwrap_or(5),
+        walls: args
+            .walls
+            .or(config_file.walls)
+            .unwrap_or(settings::WallMode::Wrap),
+        goal: None,
+        versus: args.versus,
+    };
+    if settings.versus && settings.level.rival_start().is_none() {
+        eprintln!("The level has no room for a second snake opposite the first one");
+        exit(1);
+    }
+    // Passing any game options skips the title screen and starts a game right away
+    let show_menu = !Args::command().get_arguments().any(|arg| {
+        let id = arg.get_id().as_str();
+        id != "config" && matches.value_source(id) == Some(ValueSource::CommandLine)
+    });
+
+    // Fail before the game starts rather than losing the recording after it
+    if let Some(path) = &args.record {
+        if let Err(e) = std::fs::File::create(path) {
+            eprintln!("Cannot write recording {}: {}", path.display(), e);
+            exit(1);
+        }
+    }
 
     let mut stdout = stdout();
 
     terminal::hook_into_terminal(&mut stdout).unwrap();
 
-    match game_loop::start_game(&mut game, &config, &mut stdout) {
-        Ok(signal) => match signal {
-            game_loop::GameLoopSignal::Ok => {
-                terminal::unmount_from_terminal(&mut stdout).unwrap();
-                println!("Unexpected error");
-            }
-            game_loop::GameLoopSignal::Exit => {
-                terminal::unmount_from_terminal(&mut stdout).unwrap();
-                println!("Thanks for playing! You scored {} points!", game.score);
-            }
-            game_loop::GameLoopSignal::GameOver => {
-                terminal::unmount_from_terminal(&mut stdout).unwrap();
-                println!("Game over! You scored {} points!", game.score);
+    let result = run(
+        &mut settings,
+        &mut campaign,
+        show_menu,
+        args.seed,
+        args.record.as_deref(),
+        &config,
+        &mut draw::renderer::TerminalRenderer::new(std::io::stdout()),
+    );
+
+    terminal::unmount_from_terminal(&mut stdout).unwrap();
+
+    match result {
+        Ok(Some(game)) => println!(
+            "Thanks for playing! You scored {} points! (seed: {})",
+            game.score, game.seed
+        ),
+        Ok(None) => println!("Thanks for playing!"),
+        Err(_) => println!("Unexpected error"),
+    }
+
+    if let Some(campaign) = &campaign {
+        let current = campaign.current();
+        println!(
+            "Campaign: reached level {}/{} ({})",
+            campaign.level + 1,
+            campaign::LEVELS.len(),
+            current.name
+        );
+    }
+}
+
+/// Switches between the title screen and games until the player quits. Returns the last
+/// game played, if any.
+fn run(
+    settings: &mut settings::GameSettings,
+    campaign: &mut Option<campaign::Campaign>,
+    mut show_menu: bool,
+    seed: Option<u64>,
+    record: Option<&Path>,
+    config: &config::Config,
+    renderer: &mut dyn draw::renderer::Renderer,
+) -> std::io::Result<Option<game::Game>> {
+    let mut last_game = None;
+    let events = &mut input::TerminalEvents;
+
+    loop {
+        if show_menu {
+            let mut menu = menu::MainMenu::new(
+                settings.difficulty,
+                settings.level.dimensions.1,
+                settings.level.dimensions.0,
+                settings.walls,
+                settings.versus,
+                // Recordings and the autopilot only know about one snake
+                record.is_none() && config.autopilot.is_none(),
+            );
+            match game_loop::show_main_menu(&mut menu, config, events, renderer)? {
+                menu::MenuChoice::Play(new_settings) => {
+                    *settings = new_settings;
+                    *campaign = None;
+                }
+                menu::MenuChoice::Campaign => {
+                    *campaign = Some(campaign::Campaign::new(campaign::load_unlocked() - 1))
+                }
+                menu::MenuChoice::Quit => return Ok(last_game),
             }
-        },
-        Err(_) => {
-            terminal::unmount_from_terminal(&mut stdout).unwrap();
-            println!("Unexpected error");
+        }
+
+        let (game, choice) = play(
+            settings,
+            seed,
+            record,
+            campaign.as_mut(),
+            config,
+            events,
+            renderer,
+        )?;
+        last_game = Some(game);
+
+        match choice {
+            game_loop::GameOverChoice::Menu => show_menu = true,
+            _ => return Ok(last_game),
+        }
+    }
+}
+
+/// Plays games with the same settings until the player quits or goes back to the menu.
+/// Returns the last game played and what the player chose to do after it.
+fn play(
+    settings: &settings::GameSettings,
+    seed: Option<u64>,
+    record: Option<&Path>,
+    mut campaign: Option<&mut campaign::Campaign>,
+    config: &config::Config,
+    events: &mut dyn input::EventSource,
+    renderer: &mut dyn draw::renderer::Renderer,
+) -> std::io::Result<(game::Game, game_loop::GameOverChoice)> {
+    let current_settings = |campaign: &Option<&mut campaign::Campaign>| match campaign {
+        Some(campaign) => campaign.settings(),
+        None => settings.clone(),
+    };
+
+    loop {
+        let seed = seed.unwrap_or_else(rand::random);
+        let mut game = game::Game::new(&current_settings(&campaign), seed);
+
+        let signal =
+            game_loop::start_game(&mut game, campaign.as_deref_mut(), config, events, renderer)?;
+
+        // In a campaign, the game may have ended on a later level than it started on
+        let game_settings = current_settings(&campaign);
+        if let Some(path) = record {
+            // There is no good place to report the error on the game screen
+            let _ = replay::Replay::new(&game_settings, &game).save(path);
+        }
+
+        let game_loop::GameLoopSignal::GameOver(reason) = signal else {
+            return Ok((game, game_loop::GameOverChoice::Quit));
+        };
+
+        let key = scores::ScoreKey::from_settings(&game_settings);
+        let mut table = scores::ScoreTable::load();
+        let mut summary = game_loop::GameSummary {
+            reason,
+            score: game.score,
+            length: game.snake_length(),
+            rival_score: game.rival().map(|rival| rival.score),
+            time_played: game.time_played,
+            best: table.best(&key),
+        };
+
+        // Only the player's own games make it into the high score table
+        let is_solo_game = config.autopilot.is_none() && !game_settings.versus;
+        if is_solo_game && table.is_high_score(&key, game.score) {
+            let name = game_loop::ask_for_name(&summary, config, events, renderer)?;
+            table.insert(key.clone(), game.score, &name);
+            // There is no good place to report the error on the game screen
+            let _ = table.save();
+            summary.best = table.best(&key);
+        }
+
+        match game_loop::show_game_over(&summary, config, events, renderer)? {
+            game_loop::GameOverChoice::Restart => continue,
+            choice => return Ok((game, choice)),
+        }
+    }
+}
+
+/// Plays back a recording, then checks that it still ends with the recorded score.
+fn watch_replay(path: &Path, config: &config::Config) {
+    let replay = match replay::Replay::load(path) {
+        Ok(replay) => replay,
+        Err(e) => {
+            eprintln!("Cannot load replay {}: {}", path.display(), e);
+            exit(1);
         }
     };
+    let mut playback = replay::Playback::new(replay);
+
+    let mut stdout = stdout();
+    terminal::hook_into_terminal(&mut stdout).unwrap();
+    let result = game_loop::watch_replay(
+        &mut playback,
+        config,
+        &mut input::TerminalEvents,
+        &mut draw::renderer::TerminalRenderer::new(std::io::stdout()),
+    );
+    terminal::unmount_from_terminal(&mut stdout).unwrap();
+
+    if result.is_err() {
+        println!("Unexpected error");
+        exit(1);
+    }
+
+    // The rest of the game is checked even if the player quit early
+    playback.finish();
+    match playback.verify() {
+        Ok(()) => println!(
+            "Replay matches the recording: {} points after {} moves.",
+            playback.game.score, playback.game.steps
+        ),
+        Err(e) => {
+            eprintln!("Replay does not match the recording: {}", e);
+            exit(1);
+        }
+    }
+}
+
+/// Hosts games until the process is stopped.
+fn serve(options: net::server::ServerOptions, port: u16) {
+    let server = match net::server::Server::bind(("0.0.0.0", port), options) {
+        Ok(server) => server,
+        Err(e) => {
+            eprintln!("Cannot listen on port {}: {}", port, e);
+            exit(1);
+        }
+    };
+
+    println!(
+        "Waiting for players on port {}, join with `snake-rs join <host>:{}`",
+        server.local_addr().port(),
+        server.local_addr().port()
+    );
+    server.run();
+    eprintln!("Stopped accepting connections");
+    exit(1);
+}
+
+/// Plays or watches an online game until the player leaves.
+fn join(options: &net::client::JoinOptions, config: &config::Config) {
+    let mut stdout = stdout();
+    terminal::hook_into_terminal(&mut stdout).unwrap();
+    let result = net::client::join(
+        options,
+        config,
+        &mut input::TerminalEvents,
+        &mut draw::renderer::TerminalRenderer::new(std::io::stdout()),
+    );
+    terminal::unmount_from_terminal(&mut stdout).unwrap();
+
+    match result {
+        Ok(()) => println!("Thanks for playing!"),
+        Err(e) => {
+            eprintln!("Cannot play on {}: {}", options.address, e);
+            exit(1);
+        }
+    }
+}
+
+fn load_level(path: &Path) -> level::Level {
+    match level::Level::load(path) {
+        Ok(level) => level,
+        Err(e) => {
+            eprintln!("Cannot load level {}: {}", path.display(), e);
+            exit(1);
+        }
+    }
+}
+
+/// Plays the benchmark games and prints the report.
+fn run_bench(
+    settings: &settings::GameSettings,
+    strategy: autopilot::Strategy,
+    first_seed: u64,
+    games: u64,
+    jobs: usize,
+    format: bench::Format,
+) {
+    let seeds: Vec<u64> = (0..games).map(|i| first_seed.wrapping_add(i)).collect();
+
+    let start = std::time::Instant::now();
+    let results = bench::run(settings, strategy, &seeds, jobs);
+    let report = bench::Report::new(settings, strategy, &results, start.elapsed());
+
+    match format {
+        bench::Format::Table => println!("{}", report),
+        bench::Format::Json => println!("{}", report.to_json()),
+    }
+}
+
+fn print_scores() {
+    let table = scores::ScoreTable::load();
+    let keys = table.keys();
+
+    if keys.is_empty() {
+        println!("No high scores yet.");
+        return;
+    }
+
+    for key in keys {
+        println!("{}", key);
+        for (i, entry) in table.ranking(&key).iter().enumerate() {
+            println!("  {:>2}. {:<16} {:>6}", i + 1, entry.name, entry.score);
+        }
+    }
 }
@@ -0,0 +1,167 @@
+use crate::level::Level;
+use crate::settings::{GameSettings, WallMode};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum MenuItem {
+    Play,
+    Campaign,
+    Difficulty,
+    Width,
+    Height,
+    Walls,
+    Players,
+    Quit,
+}
+
+const ITEMS: [MenuItem; 8] = [
+    MenuItem::Play,
+    MenuItem::Campaign,
+    MenuItem::Difficulty,
+    MenuItem::Width,
+    MenuItem::Height,
+    MenuItem::Walls,
+    MenuItem::Players,
+    MenuItem::Quit,
+];
+
+/// What the player picked on the title screen.
+pub enum MenuChoice {
+    Play(GameSettings),
+    Campaign,
+    Quit,
+}
+
+/// State of the title screen: the highlighted item and the settings picked so far.
+pub struct MainMenu {
+    items: Vec<MenuItem>,
+    selected: usize,
+    pub difficulty: u16,
+    pub width: u16,
+    pub height: u16,
+    pub walls: WallMode,
+    /// Two players on one board
+    pub versus: bool,
+}
+
+impl MainMenu {
+    /// Without `allow_versus`, the number of players can not be changed and only
+    /// single-player games are started.
+    pub fn new(
+        difficulty: u16,
+        width: u16,
+        height: u16,
+        walls: WallMode,
+        versus: bool,
+        allow_versus: bool,
+    ) -> MainMenu {
+        let items = ITEMS
+            .into_iter()
+            .filter(|item| allow_versus || *item != MenuItem::Players)
+            .collect();
+
+        MainMenu {
+            items,
+            selected: 0,
+            difficulty,
+            width,
+            height,
+            walls,
+            versus: versus && allow_versus,
+        }
+    }
+
+    pub fn items(&self) -> impl Iterator<Item = (MenuItem, bool)> + '_ {
+        self.items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| (*item, i == self.selected))
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selected = (self.selected + self.items.len() - 1) % self.items.len();
+    }
+
+    pub fn select_next(&mut self) {
+        self.selected = (self.selected + 1) % self.items.len();
+    }
+
+    /// Changes the highlighted setting one step up or down.
+    pub fn change(&mut self, increase: bool) {
+        let step = |value: u16, min: u16, max: u16| {
+            if increase {
+                (value + 1).min(max)
+            } else {
+                value.saturating_sub(1).max(min)
+            }
+        };
+
+        match self.items[self.selected] {
+            MenuItem::Difficulty => self.difficulty = step(self.difficulty, 1, 9),
+            MenuItem::Width => self.width = step(self.width, 3, 255),
+            MenuItem::Height => self.height = step(self.height, 3, 255),
+            MenuItem::Walls => {
+                self.walls = match self.walls {
+                    WallMode::Solid => WallMode::Wrap,
+                    WallMode::Wrap => WallMode::Solid,
+                }
+            }
+            MenuItem::Players => self.versus = !self.versus,
+            _ => {}
+        }
+    }
+
+    /// Activates the highlighted item, `None` if it is a setting rather than an action.
+    pub fn activate(&self) -> Option<MenuChoice> {
+        match self.items[self.selected] {
+            MenuItem::Play => Some(MenuChoice::Play(GameSettings {
+                level: Level::empty((self.height, self.width)),
+                difficulty: self.difficulty,
+                walls: self.walls,
+                goal: None,
+                versus: self.versus,
+            })),
+            MenuItem::Campaign => Some(MenuChoice::Campaign),
+            MenuItem::Quit => Some(MenuChoice::Quit),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn menu(allow_versus: bool) -> MainMenu {
+        MainMenu::new(5, 16, 8, WallMode::Wrap, true, allow_versus)
+    }
+
+    fn select(menu: &mut MainMenu, item: MenuItem) {
+        while menu.items().find(|(_, selected)| *selected).unwrap().0 != item {
+            menu.select_next();
+        }
+    }
+
+    #[test]
+    fn players_can_be_changed_when_versus_is_allowed() {
+        let mut menu = menu(true);
+        select(&mut menu, MenuItem::Players);
+        menu.change(true);
+        assert!(!menu.versus);
+
+        select(&mut menu, MenuItem::Play);
+        let Some(MenuChoice::Play(settings)) = menu.activate() else {
+            panic!("Play did not start a game");
+        };
+        assert!(!settings.versus);
+    }
+
+    #[test]
+    fn players_are_hidden_when_versus_is_not_allowed() {
+        let mut menu = menu(false);
+
+        assert!(menu.items().all(|(item, _)| item != MenuItem::Players));
+        assert!(!menu.versus);
+        menu.select_previous();
+        assert!(menu.items().last().unwrap() == (MenuItem::Quit, true));
+    }
+}
@@ -0,0 +1,5 @@
+//! Versus games over the network: `snake-rs serve` runs the game, and every player and
+//! spectator connects to it with `snake-rs join`.
+pub mod client;
+pub mod protocol;
+pub mod server;
@@ -0,0 +1,284 @@
+//! The client of online versus games, started with `snake-rs join`.
+//!
+//! The client follows the server's game step by step and sends the player's turns to it.
+//! If the connection drops, it keeps trying to get back in with the seat's token for
+//! [`RECONNECT_FOR`], while the server holds the game.
+use std::io;
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::time::{Duration, Instant};
+
+use super::protocol::PROTOCOL_VERSION;
+use super::protocol::{apply_step, read_messages, send, ClientMessage, ServerMessage};
+use crate::config::Config;
+use crate::direction::Direction;
+use crate::draw::renderer::Renderer;
+use crate::game::Game;
+use crate::input::{collect_input, EventSource};
+use crate::keymap::Action;
+
+/// How long the client tries to get back to the server after losing the connection.
+pub const RECONNECT_FOR: Duration = Duration::from_secs(30);
+
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// How long the client waits for key presses before looking for messages.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+pub struct JoinOptions {
+    /// `host:port` of the server
+    pub address: String,
+    pub name: String,
+    /// Watch instead of taking a seat
+    pub spectate: bool,
+}
+
+/// What is going on in the server's game, as far as the client knows.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SessionState {
+    /// Waiting for both seats to be taken
+    Lobby,
+    Playing,
+    /// Waiting for a player to get back
+    Paused {
+        waiting_for: u8,
+    },
+    Over {
+        reason: String,
+        scores: [u32; 2],
+    },
+    /// Trying to get back to the server
+    Reconnecting,
+}
+
+/// The client's copy of the server's game, with who is taking part in it.
+pub struct Session {
+    /// 1 or 2 for a player, `None` for a spectator
+    pub seat: Option<u8>,
+    token: Option<String>,
+    /// Names of the players in seats 1 and 2
+    pub players: [Option<String>; 2],
+    pub spectators: usize,
+    /// The current or last game
+    pub game: Option<Game>,
+    pub state: SessionState,
+}
+
+impl Session {
+    pub fn new() -> Session {
+        Session {
+            seat: None,
+            token: None,
+            players: [None, None],
+            spectators: 0,
+            game: None,
+            state: SessionState::Lobby,
+        }
+    }
+
+    /// The first message to the server, which takes back the seat after a reconnect.
+    fn hello(&self, options: &JoinOptions) -> ClientMessage {
+        ClientMessage::Hello {
+            version: PROTOCOL_VERSION,
+            name: options.name.clone(),
+            spectate: options.spectate,
+            token: self.token.clone(),
+        }
+    }
+
+    /// Takes a message from the server into account. Fails with the server's error
+    /// message, or if the game it starts can not be set up.
+    pub fn handle(&mut self, message: ServerMessage) -> Result<(), String> {
+        match message {
+            ServerMessage::Welcome { seat, token, .. } => {
+                self.seat = seat;
+                self.token = token;
+                self.state = SessionState::Lobby;
+            }
+            ServerMessage::Lobby {
+                players,
+                spectators,
+            } => {
+                self.players = players;
+                self.spectators = spectators;
+            }
+            ServerMessage::Start(start) => {
+                let game = start.game().ok_or("The server's level can not be read")?;
+                self.game = Some(game);
+                self.state = SessionState::Playing;
+            }
+            ServerMessage::Step { turns } => {
+                if let Some(game) = &mut self.game {
+                    apply_step(game, &turns);
+                }
+            }
+            ServerMessage::Paused { waiting_for } => {
+                self.state = SessionState::Paused { waiting_for }
+            }
+            ServerMessage::Resumed => self.state = SessionState::Playing,
+            ServerMessage::GameOver { reason, scores } => {
+                self.state = SessionState::Over { reason, scores }
+            }
+            ServerMessage::Error { message } => return Err(message),
+        }
+        Ok(())
+    }
+}
+
+/// A connection to the server that said hello.
+struct Connection {
+    stream: TcpStream,
+    messages: Receiver<Result<ServerMessage, String>>,
+}
+
+impl Connection {
+    fn open(address: SocketAddr, hello: &ClientMessage) -> io::Result<Connection> {
+        let mut stream = TcpStream::connect_timeout(&address, CONNECT_TIMEOUT)?;
+        stream.set_nodelay(true)?;
+        send(&mut stream, hello)?;
+
+        // The channel closes together with the connection
+        let (sender, messages) = mpsc::channel();
+        read_messages(
+            stream.try_clone()?,
+            move |message| sender.send(message).is_ok(),
+            || {},
+        );
+        Ok(Connection { stream, messages })
+    }
+}
+
+/// Joins the game on the server and shows it until the player leaves. Fails if the
+/// server can not be reached, turns the client away, or does not come back after the
+/// connection dropped.
+pub fn join(
+    options: &JoinOptions,
+    config: &Config,
+    events: &mut dyn EventSource,
+    renderer: &mut dyn Renderer,
+) -> io::Result<()> {
+    let address = options
+        .address
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| io::Error::other(format!("Cannot find {}", options.address)))?;
+    let mut session = Session::new();
+    let mut connection = Some(Connection::open(address, &session.hello(options))?);
+    let mut lost_at = Instant::now();
+    let mut next_attempt = lost_at;
+
+    renderer.clear()?;
+    let mut last_draw_time = Instant::now();
+    let mut needs_redraw = true;
+    loop {
+        if needs_redraw {
+            let now = Instant::now();
+            renderer.draw_online(&session, config, now - last_draw_time)?;
+            last_draw_time = now;
+            needs_redraw = false;
+        }
+
+        let input = collect_input(events, &config.keymap, Some(POLL_INTERVAL))?;
+        if input.resized.is_some() {
+            renderer.clear()?;
+            needs_redraw = true;
+        }
+        for action in input.actions {
+            let direction = match action {
+                Action::Quit | Action::Pause => return Ok(()),
+                Action::Up => Direction::Up,
+                Action::Down => Direction::Down,
+                Action::Left => Direction::Left,
+                Action::Right => Direction::Right,
+                _ => continue,
+            };
+            if let Some(connection) = &mut connection {
+                if session.seat.is_some() && session.state == SessionState::Playing {
+                    // A lost connection shows up on the reading side
+                    let _ = send(&mut connection.stream, &ClientMessage::Turn { direction });
+                }
+            }
+        }
+
+        let Some(open) = &connection else {
+            if lost_at.elapsed() > RECONNECT_FOR {
+                return Err(io::Error::other("Lost the connection to the server"));
+            }
+            if Instant::now() >= next_attempt {
+                next_attempt = Instant::now() + RECONNECT_INTERVAL;
+                connection = Connection::open(address, &session.hello(options)).ok();
+            }
+            continue;
+        };
+        loop {
+            match open.messages.try_recv() {
+                Ok(Ok(message)) => {
+                    session.handle(message).map_err(io::Error::other)?;
+                    needs_redraw = true;
+                }
+                // Messages from a newer server that this client does not know
+                Ok(Err(_)) => {}
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    connection = None;
+                    session.state = SessionState::Reconnecting;
+                    lost_at = Instant::now();
+                    next_attempt = lost_at;
+                    needs_redraw = true;
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::Level;
+    use crate::net::protocol::GameStart;
+    use crate::settings::{GameSettings, WallMode};
+
+    #[test]
+    fn session_follows_the_steps_of_the_server() {
+        let settings = GameSettings {
+            versus: true,
+            ..GameSettings::for_test(Level::empty((8, 16)), WallMode::Wrap)
+        };
+        let start = GameStart::new(&settings, 5, 3);
+        let turns = [
+            [vec![Direction::Right], vec![]],
+            [vec![], vec![Direction::Left, Direction::Up]],
+            [vec![], vec![]],
+        ];
+
+        let mut server_game = start.game().unwrap();
+        let mut session = Session::new();
+        session.handle(ServerMessage::Start(start)).unwrap();
+        for turns in turns {
+            apply_step(&mut server_game, &turns);
+            session.handle(ServerMessage::Step { turns }).unwrap();
+        }
+
+        let game = session.game.as_ref().unwrap();
+        assert_eq!(game.steps, 3);
+        assert_eq!(game.snake_body(), server_game.snake_body());
+        assert_eq!(game.food, server_game.food);
+        assert_eq!(session.state, SessionState::Playing);
+    }
+
+    #[test]
+    fn server_errors_end_the_session() {
+        let mut session = Session::new();
+        let error = ServerMessage::Error {
+            message: "The server speaks protocol version 1, not 2".to_string(),
+        };
+
+        assert_eq!(
+            session.handle(error),
+            Err("The server speaks protocol version 1, not 2".to_string())
+        );
+    }
+}
@@ -0,0 +1,201 @@
+//! Messages between the server and its clients.
+//!
+//! Both sides send one JSON object per line, with its kind in the `type` field, and give
+//! up on a connection that sends a line longer than [`MAX_LINE_LENGTH`]. A client opens
+//! with `hello`:
+//!
+//! ```text
+//! {"type":"hello","version":1,"name":"ada","spectate":false,"token":null}
+//! ```
+//!
+//! The server answers with `welcome`, giving the seat (`1` or `2`, `null` for a spectator)
+//! and a token that takes the seat back after a lost connection, or with `error` and
+//! closes the connection if the versions differ. Players join the first free seat; once
+//! both seats are taken, everyone else joins as a spectator.
+//!
+//! The server is the only one running the game on its own clock. Every client runs a copy
+//! of it in lockstep: `start` carries the settings and the seed, and every `step` carries
+//! the turns each player made before that move, which the client applies in order before
+//! moving the snakes (see [`apply_step`]). A client that joins during a game gets `start`
+//! and all steps so far. Players send their turns with `turn`:
+//!
+//! ```text
+//! {"type":"turn","direction":"up"}
+//! ```
+//!
+//! The other messages from the server are `lobby` (who sits where, sent whenever someone
+//! joins or leaves), `paused` (a player lost their connection and the game waits for
+//! them), `resumed`, and `game_over`.
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::thread;
+
+use crate::direction::Direction;
+use crate::game::{Game, StepOutcome};
+use crate::level::Level;
+use crate::settings::{GameSettings, WallMode};
+
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Longest line a message may take up, the largest messages carry a level map. The
+/// connection is given up on a longer one.
+pub const MAX_LINE_LENGTH: usize = 1 << 20;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    Hello {
+        version: u32,
+        name: String,
+        #[serde(default)]
+        spectate: bool,
+        /// Token of a seat to take back
+        #[serde(default)]
+        token: Option<String>,
+    },
+    Turn {
+        direction: Direction,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    Welcome {
+        version: u32,
+        seat: Option<u8>,
+        token: Option<String>,
+    },
+    Lobby {
+        /// Names of the players in seats 1 and 2
+        players: [Option<String>; 2],
+        spectators: usize,
+    },
+    Start(GameStart),
+    Step {
+        /// Turns of the players in seats 1 and 2
+        turns: [Vec<Direction>; 2],
+    },
+    Paused {
+        waiting_for: u8,
+    },
+    Resumed,
+    GameOver {
+        reason: String,
+        scores: [u32; 2],
+    },
+    Error {
+        message: String,
+    },
+}
+
+/// Everything a client needs to set up the same game as the server.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GameStart {
+    pub seed: u64,
+    pub width: u16,
+    pub height: u16,
+    pub walls: WallMode,
+    pub difficulty: u16,
+    pub input_buffer: usize,
+    /// The level in the format of [`Level::to_map`], `None` for an empty board
+    pub map: Option<String>,
+}
+
+impl GameStart {
+    pub fn new(settings: &GameSettings, seed: u64, input_buffer: usize) -> GameStart {
+        GameStart {
+            seed,
+            width: settings.level.dimensions.1,
+            height: settings.level.dimensions.0,
+            walls: settings.walls,
+            difficulty: settings.difficulty,
+            input_buffer,
+            map: settings
+                .level
+                .name
+                .as_ref()
+                .map(|_| settings.level.to_map()),
+        }
+    }
+
+    /// The game as it is before the first move. `None` if the map can not be read.
+    pub fn game(&self) -> Option<Game> {
+        let level = match &self.map {
+            Some(map) => Level::parse("online", map).ok()?,
+            None => Level::empty((self.height, self.width)),
+        };
+        let settings = GameSettings {
+            level,
+            difficulty: self.difficulty,
+            walls: self.walls,
+            goal: None,
+            versus: true,
+        };
+
+        let mut game = Game::new(&settings, self.seed);
+        game.set_input_buffer(self.input_buffer);
+        Some(game)
+    }
+}
+
+/// Applies the players' turns, then moves the snakes. The server and every client do
+/// exactly this for every step, so their games stay the same.
+pub fn apply_step(game: &mut Game, turns: &[Vec<Direction>; 2]) -> StepOutcome {
+    for direction in &turns[0] {
+        game.change_direction(*direction);
+    }
+    for direction in &turns[1] {
+        game.change_rival_direction(*direction);
+    }
+    game.step()
+}
+
+pub fn send<M: Serialize>(stream: &mut TcpStream, message: &M) -> io::Result<()> {
+    let mut line = serde_json::to_string(message)?;
+    line.push('\n');
+    stream.write_all(line.as_bytes())
+}
+
+/// Reads messages from `stream` on a background thread and hands them to `deliver`, or
+/// the line that could not be read as a message. Stops when the connection closes or
+/// fails, a line is longer than [`MAX_LINE_LENGTH`] or `deliver` returns `false`, then
+/// calls `closed`.
+pub fn read_messages<M, F, C>(stream: TcpStream, mut deliver: F, closed: C)
+where
+    M: DeserializeOwned,
+    F: FnMut(Result<M, String>) -> bool + Send + 'static,
+    C: FnOnce() + Send + 'static,
+{
+    thread::spawn(move || {
+        let mut reader = BufReader::new(stream);
+        loop {
+            let mut bytes = Vec::new();
+            // Reads no more than one byte past the limit, so a line that never ends is
+            // not kept in memory
+            let limit = MAX_LINE_LENGTH as u64 + 1;
+            match (&mut reader).take(limit).read_until(b'\n', &mut bytes) {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {}
+            }
+            if bytes.last() == Some(&b'\n') {
+                bytes.pop();
+                if bytes.last() == Some(&b'\r') {
+                    bytes.pop();
+                }
+            } else if bytes.len() > MAX_LINE_LENGTH {
+                break;
+            }
+
+            let Ok(line) = String::from_utf8(bytes) else {
+                break;
+            };
+            let message = serde_json::from_str(&line).map_err(|_| line);
+            if !deliver(message) {
+                break;
+            }
+        }
+        closed();
+    });
+}
@@ -0,0 +1,806 @@
+//! The server of online versus games, started with `snake-rs serve`.
+//!
+//! The server owns the game: it moves the snakes on its own clock and sends every move to
+//! all clients, which only ever send their turns (see [`protocol`](super::protocol)). A
+//! game starts as soon as both seats are taken. When a player's connection drops during
+//! a game, the game pauses until they come back with their token or the reconnect
+//! timeout runs out, which loses them the game. After a game, the next one starts with
+//! the same players, unless one of them has left.
+//!
+//! Every connection has its own writer thread, so a slow client never holds up the game.
+//! A client that falls too far behind is disconnected.
+use std::collections::HashMap;
+use std::io;
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, SyncSender, TrySendError};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::protocol::{
+    apply_step, read_messages, send, ClientMessage, GameStart, ServerMessage, PROTOCOL_VERSION,
+};
+use crate::direction::Direction;
+use crate::game::Game;
+use crate::scores::clean_name;
+use crate::settings::GameSettings;
+use crate::timestep::FixedTimestep;
+
+/// Most turns a player can make before a single move, more are dropped.
+const MAX_TURNS_PER_STEP: usize = 8;
+
+/// Most batches of messages that can wait for a client before it is disconnected.
+const OUTGOING_QUEUE: usize = 128;
+
+pub struct ServerOptions {
+    /// Board, speed and walls of every game
+    pub settings: GameSettings,
+    pub input_buffer: usize,
+    /// How long a game waits for a player whose connection dropped
+    pub reconnect_timeout: Duration,
+    /// Pause between the end of a game and the start of the next one
+    pub next_game_delay: Duration,
+    /// How long a new connection may take to say hello before it is closed
+    pub hello_timeout: Duration,
+}
+
+enum Event {
+    Connected(usize, TcpStream),
+    Message(usize, ClientMessage),
+    /// A line that is not a message
+    Invalid(usize, String),
+    Disconnected(usize),
+}
+
+struct Connection {
+    stream: TcpStream,
+    /// Messages for the connection's writer thread, which closes the connection once
+    /// this is dropped
+    outgoing: SyncSender<Vec<ServerMessage>>,
+    /// Whether the client said hello
+    greeted: bool,
+}
+
+struct Seat {
+    name: String,
+    token: String,
+    /// `None` while the player is disconnected
+    connection: Option<usize>,
+}
+
+enum Phase {
+    /// Waiting for both seats to be taken
+    Lobby,
+    Playing(Match),
+    /// The game ended with `result`, the next one starts at `until`
+    Over {
+        game: Match,
+        result: ServerMessage,
+        until: Instant,
+    },
+}
+
+/// A game in progress, with everything needed to bring a client up to date.
+struct Match {
+    start: GameStart,
+    game: Game,
+    /// The turns of every move so far
+    steps: Vec<[Vec<Direction>; 2]>,
+    /// Turns made since the last move
+    pending: [Vec<Direction>; 2],
+    timestep: FixedTimestep,
+    /// When the game was paused for a disconnected player
+    paused_since: Option<Instant>,
+}
+
+pub struct Server {
+    address: SocketAddr,
+    options: ServerOptions,
+    events: Receiver<Event>,
+    connections: HashMap<usize, Connection>,
+    seats: [Option<Seat>; 2],
+    phase: Phase,
+    last_update: Instant,
+}
+
+impl Server {
+    /// Starts listening on `address`. Clients are served once [`Server::run`] is called.
+    pub fn bind(address: impl ToSocketAddrs, options: ServerOptions) -> io::Result<Server> {
+        let listener = TcpListener::bind(address)?;
+        let address = listener.local_addr()?;
+
+        let (sender, events) = mpsc::channel();
+        let hello_timeout = options.hello_timeout;
+        thread::spawn(move || accept_connections(listener, sender, hello_timeout));
+
+        Ok(Server {
+            address,
+            options,
+            events,
+            connections: HashMap::new(),
+            seats: [None, None],
+            phase: Phase::Lobby,
+            last_update: Instant::now(),
+        })
+    }
+
+    /// The address the server listens on.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Serves clients for as long as connections can be accepted.
+    pub fn run(mut self) {
+        loop {
+            let event = match self.until_next_update() {
+                Some(timeout) => self.events.recv_timeout(timeout),
+                None => self
+                    .events
+                    .recv()
+                    .map_err(|_| RecvTimeoutError::Disconnected),
+            };
+            match event {
+                Ok(event) => self.handle(event),
+                Err(RecvTimeoutError::Timeout) => {}
+                // The listener failed
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+            self.update(Instant::now());
+        }
+    }
+
+    /// Time until the game needs the server's attention, `None` if only a client can
+    /// change anything.
+    fn until_next_update(&self) -> Option<Duration> {
+        let now = Instant::now();
+        match &self.phase {
+            Phase::Lobby => None,
+            Phase::Playing(game) => Some(match game.paused_since {
+                Some(since) => {
+                    (since + self.options.reconnect_timeout).saturating_duration_since(now)
+                }
+                None => game.timestep.until_next_step(),
+            }),
+            Phase::Over { until, .. } => Some(until.saturating_duration_since(now)),
+        }
+    }
+
+    fn handle(&mut self, event: Event) {
+        match event {
+            Event::Connected(id, stream) => {
+                // Moves are small and have to arrive right away
+                let _ = stream.set_nodelay(true);
+                let Ok(writer) = stream.try_clone() else {
+                    let _ = stream.shutdown(Shutdown::Both);
+                    return;
+                };
+                let (outgoing, messages) = mpsc::sync_channel(OUTGOING_QUEUE);
+                write_messages(writer, messages);
+                let connection = Connection {
+                    stream,
+                    outgoing,
+                    greeted: false,
+                };
+                self.connections.insert(id, connection);
+            }
+            Event::Message(id, message) => self.receive(id, message),
+            Event::Invalid(id, line) => {
+                self.reject(id, format!("Cannot read message: {}", line));
+            }
+            Event::Disconnected(id) => self.disconnected(id),
+        }
+    }
+
+    fn receive(&mut self, id: usize, message: ClientMessage) {
+        let Some(connection) = self.connections.get_mut(&id) else {
+            return;
+        };
+
+        match message {
+            ClientMessage::Hello {
+                version,
+                name,
+                spectate,
+                token,
+            } => {
+                if version != PROTOCOL_VERSION {
+                    let message = format!(
+                        "The server speaks protocol version {}, not {}",
+                        PROTOCOL_VERSION, version
+                    );
+                    return self.reject(id, message);
+                }
+                if connection.greeted {
+                    return self.reject(id, "Said hello twice".to_string());
+                }
+                connection.greeted = true;
+                self.welcome(id, name, spectate, token);
+            }
+            ClientMessage::Turn { direction } => {
+                if !connection.greeted {
+                    return self.reject(id, "Expected hello".to_string());
+                }
+                let seat = self.seat_of(id);
+                if let (Some(seat), Phase::Playing(game)) = (seat, &mut self.phase) {
+                    let pending = &mut game.pending[seat];
+                    if pending.len() < MAX_TURNS_PER_STEP {
+                        pending.push(direction);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Seats the new client, or makes them a spectator, and brings them up to date.
+    fn welcome(&mut self, id: usize, name: String, spectate: bool, token: Option<String>) {
+        let name = clean_name(&name);
+        let reclaimed = token.and_then(|token| {
+            self.seats
+                .iter()
+                .position(|seat| seat.as_ref().is_some_and(|seat| seat.token == token))
+        });
+        let free = (!spectate)
+            .then(|| self.seats.iter().position(Option::is_none))
+            .flatten();
+
+        let seat = match (reclaimed, free) {
+            (Some(seat), _) => {
+                let seat_state = self.seats[seat].as_mut().unwrap();
+                // The old connection may not have noticed yet that it is gone, dropping it
+                // closes it
+                if let Some(old) = seat_state.connection.replace(id) {
+                    self.connections.remove(&old);
+                }
+                println!("{} is back as Player {}", seat_state.name, seat + 1);
+                Some(seat)
+            }
+            (None, Some(seat)) => {
+                println!("{} joined as Player {}", name, seat + 1);
+                self.seats[seat] = Some(Seat {
+                    name,
+                    token: new_token(),
+                    connection: Some(id),
+                });
+                Some(seat)
+            }
+            (None, None) => {
+                println!("{} is watching", name);
+                None
+            }
+        };
+
+        let welcome = ServerMessage::Welcome {
+            version: PROTOCOL_VERSION,
+            seat: seat.map(|seat| seat as u8 + 1),
+            token: seat.map(|seat| self.seats[seat].as_ref().unwrap().token.clone()),
+        };
+        self.send_to(id, &welcome);
+        self.broadcast(&self.lobby());
+
+        // Everything that happened in the current game, so the client can follow it
+        let history = match &self.phase {
+            Phase::Lobby => vec![],
+            Phase::Playing(game) => game.history(),
+            Phase::Over { game, result, .. } => {
+                let mut history = game.history();
+                history.push(result.clone());
+                history
+            }
+        };
+        if !history.is_empty() {
+            self.queue(id, history);
+        }
+
+        if let Phase::Playing(game) = &mut self.phase {
+            if game.paused_since.is_some() {
+                match self.seats.iter().position(is_disconnected) {
+                    Some(seat) => self.broadcast(&ServerMessage::Paused {
+                        waiting_for: seat as u8 + 1,
+                    }),
+                    None => {
+                        game.paused_since = None;
+                        self.last_update = Instant::now();
+                        self.broadcast(&ServerMessage::Resumed);
+                    }
+                }
+            }
+        }
+    }
+
+    fn disconnected(&mut self, id: usize) {
+        if self.connections.remove(&id).is_none() {
+            return;
+        }
+        let Some(seat) = self.seat_of(id) else {
+            return;
+        };
+
+        let seat_state = self.seats[seat].as_mut().unwrap();
+        seat_state.connection = None;
+        println!("{} left", seat_state.name);
+        match &mut self.phase {
+            // Outside of games, seats are only kept for the players who are there
+            Phase::Lobby => self.seats[seat] = None,
+            Phase::Playing(game) => {
+                game.paused_since.get_or_insert_with(Instant::now);
+                self.broadcast(&ServerMessage::Paused {
+                    waiting_for: seat as u8 + 1,
+                });
+            }
+            Phase::Over { .. } => {}
+        }
+        self.broadcast(&self.lobby());
+    }
+
+    /// Sends an error to the client and closes its connection once the error is sent.
+    fn reject(&mut self, id: usize, message: String) {
+        self.send_to(id, &ServerMessage::Error { message });
+        self.disconnected(id);
+    }
+
+    /// Moves the game on to wherever it should be at `now`.
+    fn update(&mut self, now: Instant) {
+        let delta = now.saturating_duration_since(self.last_update);
+        self.last_update = now;
+
+        match &mut self.phase {
+            Phase::Lobby => {
+                let all_seated = self
+                    .seats
+                    .iter()
+                    .all(|seat| seat.as_ref().is_some_and(|seat| seat.connection.is_some()));
+                if all_seated {
+                    self.start_game();
+                }
+            }
+            Phase::Playing(game) => match game.paused_since {
+                Some(since) if now - since >= self.options.reconnect_timeout => {
+                    let reason = match self.seats.iter().position(is_disconnected) {
+                        Some(seat) if is_disconnected(&self.seats[1 - seat]) => {
+                            "Both players left the game".to_string()
+                        }
+                        Some(seat) => format!("Player {} left the game", seat + 1),
+                        None => "The game was abandoned".to_string(),
+                    };
+                    self.end_game(reason);
+                }
+                Some(_) => {}
+                None => {
+                    for _ in 0..game.timestep.advance(delta) {
+                        if let Some(reason) = self.step() {
+                            return self.end_game(reason);
+                        }
+                    }
+                }
+            },
+            Phase::Over { until, .. } if now >= *until => {
+                // Players who left during the game give up their seats now
+                for seat in &mut self.seats {
+                    if is_disconnected(seat) {
+                        *seat = None;
+                    }
+                }
+                self.phase = Phase::Lobby;
+                self.broadcast(&self.lobby());
+                self.update(now);
+            }
+            Phase::Over { .. } => {}
+        }
+    }
+
+    /// Makes the next move with the turns the players made since the last one. Returns
+    /// why the game ended, if it did.
+    fn step(&mut self) -> Option<String> {
+        let Phase::Playing(game) = &mut self.phase else {
+            return None;
+        };
+        let turns = std::mem::take(&mut game.pending);
+        let outcome = apply_step(&mut game.game, &turns);
+        game.steps.push(turns.clone());
+
+        self.broadcast(&ServerMessage::Step { turns });
+        outcome.game_over.map(|reason| reason.to_string())
+    }
+
+    fn start_game(&mut self) {
+        let start = GameStart::new(
+            &self.options.settings,
+            rand::random(),
+            self.options.input_buffer,
+        );
+        // The clients build their games the same way, so nothing can differ between them
+        let game = start
+            .game()
+            .expect("the server's own level can be read back");
+        let timestep = FixedTimestep::new(game.step_interval());
+
+        println!("Game started with seed {}", start.seed);
+        self.broadcast(&ServerMessage::Start(start.clone()));
+        self.phase = Phase::Playing(Match {
+            start,
+            game,
+            steps: vec![],
+            pending: Default::default(),
+            timestep,
+            paused_since: None,
+        });
+    }
+
+    fn end_game(&mut self, reason: String) {
+        let Phase::Playing(game) = std::mem::replace(&mut self.phase, Phase::Lobby) else {
+            return;
+        };
+
+        let scores = [
+            game.game.score,
+            game.game.rival().map_or(0, |rival| rival.score),
+        ];
+        println!("Game over: {} ({} : {})", reason, scores[0], scores[1]);
+        let result = ServerMessage::GameOver { reason, scores };
+        self.broadcast(&result);
+        self.phase = Phase::Over {
+            game,
+            result,
+            until: Instant::now() + self.options.next_game_delay,
+        };
+    }
+
+    fn seat_of(&self, id: usize) -> Option<usize> {
+        self.seats.iter().position(|seat| {
+            seat.as_ref()
+                .is_some_and(|seat| seat.connection == Some(id))
+        })
+    }
+
+    fn lobby(&self) -> ServerMessage {
+        let seated = self
+            .seats
+            .iter()
+            .filter_map(|seat| seat.as_ref()?.connection);
+        let seated: Vec<usize> = seated.collect();
+        let spectators = self
+            .connections
+            .iter()
+            .filter(|(id, connection)| connection.greeted && !seated.contains(id))
+            .count();
+
+        ServerMessage::Lobby {
+            players: [0, 1].map(|seat| self.seats[seat].as_ref().map(|seat| seat.name.clone())),
+            spectators,
+        }
+    }
+
+    fn send_to(&self, id: usize, message: &ServerMessage) {
+        self.queue(id, vec![message.clone()]);
+    }
+
+    /// Sends a message to every client that said hello.
+    fn broadcast(&self, message: &ServerMessage) {
+        for connection in self.connections.values() {
+            if connection.greeted {
+                connection.queue(vec![message.clone()]);
+            }
+        }
+    }
+
+    fn queue(&self, id: usize, messages: Vec<ServerMessage>) {
+        if let Some(connection) = self.connections.get(&id) {
+            connection.queue(messages);
+        }
+    }
+}
+
+impl Connection {
+    /// Hands messages to the writer thread. A client that is too far behind is
+    /// disconnected by its reader once the connection is shut down.
+    fn queue(&self, messages: Vec<ServerMessage>) {
+        if let Err(TrySendError::Full(_)) = self.outgoing.try_send(messages) {
+            let _ = self.stream.shutdown(Shutdown::Both);
+        }
+    }
+}
+
+impl Match {
+    /// The messages that set up the game on a client as it is now.
+    fn history(&self) -> Vec<ServerMessage> {
+        let mut messages = vec![ServerMessage::Start(self.start.clone())];
+        messages.extend(self.steps.iter().map(|turns| ServerMessage::Step {
+            turns: turns.clone(),
+        }));
+        messages
+    }
+}
+
+fn is_disconnected(seat: &Option<Seat>) -> bool {
+    seat.as_ref().is_some_and(|seat| seat.connection.is_none())
+}
+
+fn new_token() -> String {
+    format!("{:016x}", rand::random::<u64>())
+}
+
+/// Writes the messages for a connection on a background thread, until the connection
+/// fails or `messages` is closed. Then it shuts down the connection.
+fn write_messages(mut stream: TcpStream, messages: Receiver<Vec<ServerMessage>>) {
+    thread::spawn(move || {
+        'connection: for batch in messages {
+            for message in &batch {
+                if send(&mut stream, message).is_err() {
+                    break 'connection;
+                }
+            }
+        }
+        let _ = stream.shutdown(Shutdown::Both);
+    });
+}
+
+/// Hands every new connection and everything it sends to the server's main loop. A
+/// connection that has not sent a whole line `hello_timeout` after it was opened is
+/// closed.
+fn accept_connections(listener: TcpListener, events: Sender<Event>, hello_timeout: Duration) {
+    for (id, stream) in listener.incoming().enumerate() {
+        let Ok(stream) = stream else { continue };
+        let (Ok(reader), Ok(watched)) = (stream.try_clone(), stream.try_clone()) else {
+            continue;
+        };
+        if events.send(Event::Connected(id, stream)).is_err() {
+            return;
+        }
+
+        let spoke = Arc::new(AtomicBool::new(false));
+        let silent = Arc::clone(&spoke);
+        thread::spawn(move || {
+            thread::sleep(hello_timeout);
+            // Ends the reader, which reports the connection as closed
+            if !silent.load(Ordering::Relaxed) {
+                let _ = watched.shutdown(Shutdown::Both);
+            }
+        });
+
+        let messages = events.clone();
+        let closed = events.clone();
+        read_messages(
+            reader,
+            move |message| {
+                spoke.store(true, Ordering::Relaxed);
+                let event = match message {
+                    Ok(message) => Event::Message(id, message),
+                    Err(line) => Event::Invalid(id, line),
+                };
+                messages.send(event).is_ok()
+            },
+            move || {
+                let _ = closed.send(Event::Disconnected(id));
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::Level;
+    use crate::net::protocol::MAX_LINE_LENGTH;
+    use crate::settings::WallMode;
+    use std::io::{BufRead, BufReader, Write};
+
+    /// Starts a server on a free port. Its snakes go along opposite edges of a wrapping
+    /// board, so the game does not end unless the players turn.
+    fn start_server(reconnect_timeout: Duration) -> SocketAddr {
+        let options = ServerOptions {
+            settings: GameSettings {
+                difficulty: 9,
+                versus: true,
+                ..GameSettings::for_test(Level::empty((8, 16)), WallMode::Wrap)
+            },
+            input_buffer: 3,
+            reconnect_timeout,
+            next_game_delay: Duration::from_secs(1),
+            hello_timeout: Duration::from_secs(1),
+        };
+        let server = Server::bind("127.0.0.1:0", options).unwrap();
+        let address = server.local_addr();
+        thread::spawn(move || server.run());
+        address
+    }
+
+    struct TestClient {
+        stream: TcpStream,
+        reader: BufReader<TcpStream>,
+    }
+
+    impl TestClient {
+        fn connect(address: SocketAddr) -> TestClient {
+            let stream = TcpStream::connect(address).unwrap();
+            stream
+                .set_read_timeout(Some(Duration::from_secs(5)))
+                .unwrap();
+            let reader = BufReader::new(stream.try_clone().unwrap());
+            TestClient { stream, reader }
+        }
+
+        /// Connects and says hello, returning the welcome.
+        fn join(
+            address: SocketAddr,
+            spectate: bool,
+            token: Option<String>,
+        ) -> (TestClient, Option<u8>, Option<String>) {
+            let mut client = TestClient::connect(address);
+            client.send(&ClientMessage::Hello {
+                version: PROTOCOL_VERSION,
+                name: String::from("test"),
+                spectate,
+                token,
+            });
+            match client.receive() {
+                Some(ServerMessage::Welcome { seat, token, .. }) => (client, seat, token),
+                message => panic!("expected a welcome, got {:?}", message),
+            }
+        }
+
+        fn send(&mut self, message: &ClientMessage) {
+            send(&mut self.stream, message).unwrap();
+        }
+
+        /// The next message, `None` once the server closed the connection.
+        fn receive(&mut self) -> Option<ServerMessage> {
+            let mut line = String::new();
+            match self.reader.read_line(&mut line).unwrap() {
+                0 => None,
+                _ => Some(serde_json::from_str(&line).unwrap()),
+            }
+        }
+
+        /// Skips messages until one matches, returning how many steps were skipped.
+        fn receive_until(
+            &mut self,
+            matches: impl Fn(&ServerMessage) -> bool,
+        ) -> (ServerMessage, usize) {
+            let mut steps = 0;
+            loop {
+                let message = self.receive().expect("the connection closed");
+                if matches(&message) {
+                    return (message, steps);
+                }
+                steps += matches!(message, ServerMessage::Step { .. }) as usize;
+            }
+        }
+    }
+
+    #[test]
+    fn other_protocol_versions_are_turned_away() {
+        let address = start_server(Duration::from_secs(1));
+        let mut client = TestClient::connect(address);
+        client.send(&ClientMessage::Hello {
+            version: PROTOCOL_VERSION + 1,
+            name: String::from("future"),
+            spectate: false,
+            token: None,
+        });
+
+        assert!(matches!(
+            client.receive(),
+            Some(ServerMessage::Error { .. })
+        ));
+        assert_eq!(client.receive(), None);
+    }
+
+    #[test]
+    fn clients_that_do_not_say_hello_are_disconnected() {
+        let address = start_server(Duration::from_secs(1));
+        let mut client = TestClient::connect(address);
+        client.stream.write_all(b"{\"type\":").unwrap();
+
+        assert_eq!(client.receive(), None);
+    }
+
+    #[test]
+    fn overlong_lines_end_the_connection() {
+        let address = start_server(Duration::from_secs(1));
+        let (mut client, _, _) = TestClient::join(address, false, None);
+        client
+            .stream
+            .write_all(&vec![b' '; MAX_LINE_LENGTH + 1])
+            .unwrap();
+
+        // Times out if the connection stays open
+        while client.receive().is_some() {}
+    }
+
+    #[test]
+    fn names_are_cleaned_up() {
+        let address = start_server(Duration::from_secs(1));
+        let mut client = TestClient::connect(address);
+        client.send(&ClientMessage::Hello {
+            version: PROTOCOL_VERSION,
+            name: String::from("\x1b[2Jada\x07 with a very long name"),
+            spectate: false,
+            token: None,
+        });
+
+        let (lobby, _) =
+            client.receive_until(|message| matches!(message, ServerMessage::Lobby { .. }));
+        let ServerMessage::Lobby { players, .. } = lobby else {
+            unreachable!()
+        };
+        assert_eq!(players[0].as_deref(), Some("[2Jada  with a"));
+    }
+
+    #[test]
+    fn spectators_see_every_turn() {
+        let address = start_server(Duration::from_secs(1));
+        let (mut spectator, seat, token) = TestClient::join(address, true, None);
+        assert_eq!((seat, token), (None, None));
+        let (mut first, seat, _) = TestClient::join(address, false, None);
+        assert_eq!(seat, Some(1));
+        let (mut second, seat, _) = TestClient::join(address, false, None);
+        assert_eq!(seat, Some(2));
+        let (third, seat, _) = TestClient::join(address, false, None);
+        assert_eq!(seat, None, "a third player watches");
+        drop(third);
+
+        let is_start = |message: &ServerMessage| matches!(message, ServerMessage::Start(_));
+        first.receive_until(is_start);
+        second.receive_until(is_start);
+        let (start, _) = spectator.receive_until(is_start);
+        let ServerMessage::Start(start) = start else {
+            unreachable!()
+        };
+
+        first.send(&ClientMessage::Turn {
+            direction: Direction::Right,
+        });
+        let (step, _) = spectator.receive_until(
+            |message| matches!(message, ServerMessage::Step { turns } if !turns[0].is_empty()),
+        );
+        assert_eq!(
+            step,
+            ServerMessage::Step {
+                turns: [vec![Direction::Right], vec![]]
+            }
+        );
+        assert_eq!(start.width, 16);
+    }
+
+    #[test]
+    fn players_get_their_seat_back_after_a_reconnect() {
+        let address = start_server(Duration::from_secs(5));
+        let (mut first, _, _) = TestClient::join(address, false, None);
+        let (second, _, token) = TestClient::join(address, false, None);
+
+        let is_start = |message: &ServerMessage| matches!(message, ServerMessage::Start(_));
+        first.receive_until(is_start);
+        second.stream.shutdown(Shutdown::Both).unwrap();
+        let (paused, steps_played) =
+            first.receive_until(|message| matches!(message, ServerMessage::Paused { .. }));
+        assert_eq!(paused, ServerMessage::Paused { waiting_for: 2 });
+
+        let (mut second, seat, new_token) = TestClient::join(address, false, token.clone());
+        assert_eq!(seat, Some(2));
+        assert_eq!(new_token, token);
+        // The game so far is sent again, so the client can catch up
+        second.receive_until(is_start);
+        let (_, steps_caught_up) =
+            second.receive_until(|message| *message == ServerMessage::Resumed);
+        assert_eq!(steps_caught_up, steps_played);
+        first.receive_until(|message| *message == ServerMessage::Resumed);
+    }
+
+    #[test]
+    fn players_who_do_not_come_back_lose() {
+        let address = start_server(Duration::from_millis(200));
+        let (mut first, _, _) = TestClient::join(address, false, None);
+        let (second, _, _) = TestClient::join(address, false, None);
+
+        first.receive_until(|message| matches!(message, ServerMessage::Start(_)));
+        drop(second);
+        let (game_over, _) =
+            first.receive_until(|message| matches!(message, ServerMessage::GameOver { .. }));
+
+        let ServerMessage::GameOver { reason, .. } = game_over else {
+            unreachable!()
+        };
+        assert_eq!(reason, "Player 2 left the game");
+    }
+}
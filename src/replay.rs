@@ -0,0 +1,535 @@
+//! Recordings of played games.
+//!
+//! Games are fully determined by their settings, the seed and the moves at which the snake
+//! turned, so that is all a recording holds. It is a plain-text file:
+//!
+//! ```text
+//! snake-replay 1
+//! seed 8410263527043162880
+//! difficulty 5
+//! walls wrap
+//! board 16 8
+//! steps 214
+//! score 45
+//! turns 0R 6D 9L 23U 25R 40D 44L 57U 63R 70D 77L 81U 92R 105D 112L 118U
+//! turns 130R 141D
+//! ```
+//!
+//! - `snake-replay` - the format version, always the first line
+//! - `seed` - seed of the game's random number generator
+//! - `difficulty` - game speed, 1-9
+//! - `walls` - `wrap` or `solid`
+//! - `goal` - optional, `length N` or `score N`
+//! - `board W H` - an empty board of the given width and height, 3-255 each, or instead
+//!   `map NAME` followed by the rows of a level map (see [`crate::level`]) and a line `end`
+//! - `steps` - number of moves the game lasted
+//! - `score` - final score, to check the replay against
+//! - `turns` - any number of lines with turns, each written as the number of the move
+//!   (counted from 0) followed by the new direction: `U`, `D`, `L` or `R`
+//!
+//! Lines starting with `;` are comments.
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::direction::Direction;
+use crate::game::{Game, GameOver};
+use crate::level::{Level, LevelError};
+use crate::settings::{GameSettings, Goal, WallMode};
+
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Number of turns written on one line.
+const TURNS_PER_LINE: usize = 16;
+
+pub struct Replay {
+    pub settings: GameSettings,
+    pub seed: u64,
+    pub steps: u64,
+    pub score: u32,
+    pub turns: Vec<(u64, Direction)>,
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(io::Error),
+    Parse { line: usize, message: String },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Io(e) => write!(f, "{}", e),
+            ReplayError::Parse { line, message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl Replay {
+    /// Records `game`, which was started with `settings`.
+    pub fn new(settings: &GameSettings, game: &Game) -> Replay {
+        Replay {
+            settings: settings.clone(),
+            seed: game.seed,
+            steps: game.steps,
+            score: game.score,
+            turns: game.turns().to_vec(),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_text())
+    }
+
+    pub fn to_text(&self) -> String {
+        let settings = &self.settings;
+        let mut lines = vec![
+            format!("snake-replay {}", FORMAT_VERSION),
+            format!("seed {}", self.seed),
+            format!("difficulty {}", settings.difficulty),
+            format!("walls {}", wall_mode_name(settings.walls)),
+        ];
+
+        match settings.goal {
+            Some(Goal::Length(length)) => lines.push(format!("goal length {}", length)),
+            Some(Goal::Score(score)) => lines.push(format!("goal score {}", score)),
+            None => {}
+        }
+
+        let level = &settings.level;
+        match &level.name {
+            Some(name) => {
+                lines.push(format!("map {}", name));
+                lines.extend(level.to_map().lines().map(String::from));
+                lines.push(String::from("end"));
+            }
+            None => lines.push(format!(
+                "board {} {}",
+                level.dimensions.1, level.dimensions.0
+            )),
+        }
+
+        lines.push(format!("steps {}", self.steps));
+        lines.push(format!("score {}", self.score));
+        for chunk in self.turns.chunks(TURNS_PER_LINE) {
+            let turns: Vec<String> = chunk
+                .iter()
+                .map(|(step, direction)| format!("{}{}", step, direction_letter(*direction)))
+                .collect();
+            lines.push(format!("turns {}", turns.join(" ")));
+        }
+
+        let mut text = lines.join("\n");
+        text.push('\n');
+        text
+    }
+
+    pub fn load(path: &Path) -> Result<Replay, ReplayError> {
+        let text = fs::read_to_string(path).map_err(ReplayError::Io)?;
+        Replay::parse(&text)
+    }
+
+    pub fn parse(text: &str) -> Result<Replay, ReplayError> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.trim_end_matches('\r')))
+            .filter(|(_, line)| !line.starts_with(';') && !line.trim().is_empty());
+
+        let mut version = None;
+        let mut seed = None;
+        let mut difficulty = None;
+        let mut walls = None;
+        let mut goal = None;
+        let mut level = None;
+        let mut steps = None;
+        let mut score = None;
+        let mut turns = vec![];
+        let mut last_line = 0;
+
+        while let Some((line_no, line)) = lines.next() {
+            last_line = line_no;
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            let value = value.trim();
+            let error = |message: String| parse_error(line_no, message);
+
+            if version.is_none() && key != "snake-replay" {
+                return Err(error(String::from("not a snake replay")));
+            }
+
+            match key {
+                "snake-replay" => {
+                    let number: u32 = parse_number(value, line_no)?;
+                    if number != FORMAT_VERSION {
+                        return Err(error(format!("unsupported version {}", number)));
+                    }
+                    version = Some(number);
+                }
+                "seed" => seed = Some(parse_number(value, line_no)?),
+                "difficulty" => {
+                    let number = parse_number(value, line_no)?;
+                    if !(1..=9).contains(&number) {
+                        return Err(error(String::from("difficulty must be between 1 and 9")));
+                    }
+                    difficulty = Some(number);
+                }
+                "walls" => {
+                    walls = Some(match value {
+                        "wrap" => WallMode::Wrap,
+                        "solid" => WallMode::Solid,
+                        other => return Err(error(format!("unknown walls '{}'", other))),
+                    })
+                }
+                "goal" => {
+                    let (kind, amount) = value.split_once(' ').unwrap_or((value, ""));
+                    goal = Some(match kind {
+                        "length" => Goal::Length(parse_number(amount, line_no)?),
+                        "score" => Goal::Score(parse_number(amount, line_no)?),
+                        other => return Err(error(format!("unknown goal '{}'", other))),
+                    })
+                }
+                "board" => {
+                    let (width, height) = value.split_once(' ').unwrap_or((value, ""));
+                    let dimensions = (
+                        parse_number(height, line_no)?,
+                        parse_number(width, line_no)?,
+                    );
+                    // Recordings are shared, so they get the same limits as the command line
+                    let size = 3..=255;
+                    if !size.contains(&dimensions.0) || !size.contains(&dimensions.1) {
+                        return Err(error(String::from("board must be between 3x3 and 255x255")));
+                    }
+                    level = Some(Level::empty(dimensions));
+                }
+                "map" => {
+                    let mut map = String::new();
+                    loop {
+                        match lines.next() {
+                            Some((_, "end")) => break,
+                            Some((_, row)) => {
+                                map.push_str(row);
+                                map.push('\n');
+                            }
+                            None => return Err(error(String::from("map has no end"))),
+                        }
+                    }
+                    let parsed = Level::parse(value, &map).map_err(|e| match e {
+                        LevelError::Parse { line, message } => {
+                            error(format!("map line {}: {}", line, message))
+                        }
+                        LevelError::Io(e) => ReplayError::Io(e),
+                    })?;
+                    level = Some(parsed);
+                }
+                "steps" => steps = Some(parse_number(value, line_no)?),
+                "score" => score = Some(parse_number(value, line_no)?),
+                "turns" => {
+                    for token in value.split_whitespace() {
+                        turns.push(
+                            parse_turn(token)
+                                .ok_or_else(|| error(format!("invalid turn '{}'", token)))?,
+                        );
+                    }
+                }
+                other => return Err(error(format!("unknown key '{}'", other))),
+            }
+        }
+
+        let missing = |name: &str| parse_error(last_line, format!("missing {}", name));
+        if version.is_none() {
+            return Err(missing("snake-replay"));
+        }
+        if turns.windows(2).any(|pair| pair[0].0 >= pair[1].0) {
+            return Err(parse_error(
+                last_line,
+                String::from("turns are not in order"),
+            ));
+        }
+
+        Ok(Replay {
+            settings: GameSettings {
+                level: level.ok_or_else(|| missing("board or map"))?,
+                difficulty: difficulty.ok_or_else(|| missing("difficulty"))?,
+                walls: walls.ok_or_else(|| missing("walls"))?,
+                goal,
+                versus: false,
+            },
+            seed: seed.ok_or_else(|| missing("seed"))?,
+            steps: steps.ok_or_else(|| missing("steps"))?,
+            score: score.ok_or_else(|| missing("score"))?,
+            turns,
+        })
+    }
+}
+
+/// Plays a recording back move by move.
+pub struct Playback {
+    pub game: Game,
+    replay: Replay,
+    next_turn: usize,
+    pub game_over: Option<GameOver>,
+}
+
+impl Playback {
+    pub fn new(replay: Replay) -> Playback {
+        Playback {
+            game: Game::new(&replay.settings, replay.seed),
+            replay,
+            next_turn: 0,
+            game_over: None,
+        }
+    }
+
+    /// Number of moves in the recording.
+    pub fn total_steps(&self) -> u64 {
+        self.replay.steps
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.game_over.is_some() || self.game.steps >= self.replay.steps
+    }
+
+    /// Makes the next recorded move, unless the playback is finished.
+    pub fn step(&mut self) {
+        if self.is_finished() {
+            return;
+        }
+
+        while let Some((step, direction)) = self.replay.turns.get(self.next_turn) {
+            if *step != self.game.steps {
+                break;
+            }
+            self.game.change_direction(*direction);
+            self.next_turn += 1;
+        }
+        self.game_over = self.game.step().game_over;
+    }
+
+    /// Makes all remaining moves at once.
+    pub fn finish(&mut self) {
+        while !self.is_finished() {
+            self.step();
+        }
+    }
+
+    /// Checks a finished playback against the end of the recording. A difference means
+    /// the rules of the game changed since it was recorded.
+    pub fn verify(&self) -> Result<(), String> {
+        let (game, replay) = (&self.game, &self.replay);
+        if game.score == replay.score && game.steps == replay.steps {
+            return Ok(());
+        }
+
+        Err(format!(
+            "the replay ended with {} points after {} moves, but the recording has {} points after {} moves",
+            game.score, game.steps, replay.score, replay.steps
+        ))
+    }
+}
+
+fn wall_mode_name(walls: WallMode) -> &'static str {
+    match walls {
+        WallMode::Solid => "solid",
+        WallMode::Wrap => "wrap",
+    }
+}
+
+fn direction_letter(direction: Direction) -> char {
+    match direction {
+        Direction::Up => 'U',
+        Direction::Down => 'D',
+        Direction::Left => 'L',
+        Direction::Right => 'R',
+    }
+}
+
+fn parse_turn(token: &str) -> Option<(u64, Direction)> {
+    let direction = match token.chars().last()? {
+        'U' => Direction::Up,
+        'D' => Direction::Down,
+        'L' => Direction::Left,
+        'R' => Direction::Right,
+        _ => return None,
+    };
+    let step = token[..token.len() - 1].parse().ok()?;
+    Some((step, direction))
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str, line: usize) -> Result<T, ReplayError> {
+    value
+        .parse()
+        .map_err(|_| parse_error(line, format!("invalid number '{}'", value)))
+}
+
+fn parse_error(line: usize, message: String) -> ReplayError {
+    ReplayError::Parse { line, message }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::level::Level;
+
+    fn settings(level: Level) -> GameSettings {
+        GameSettings {
+            difficulty: 3,
+            goal: Some(Goal::Length(12)),
+            ..GameSettings::for_test(level, WallMode::Solid)
+        }
+    }
+
+    /// Plays `settings` with the given seed, turning at the given moves.
+    fn play(settings: &GameSettings, seed: u64, steps: u64, turns: &[(u64, Direction)]) -> Game {
+        let mut game = Game::new(settings, seed);
+        for step in 0..steps {
+            for (_, direction) in turns.iter().filter(|(at, _)| *at == step) {
+                game.change_direction(*direction);
+            }
+            if game.step().game_over.is_some() {
+                break;
+            }
+        }
+        game
+    }
+
+    #[test]
+    fn recording_is_written_in_the_documented_format() {
+        let replay = Replay {
+            settings: settings(Level::empty((8, 16))),
+            seed: 42,
+            steps: 30,
+            score: 6,
+            turns: vec![(0, Direction::Right), (7, Direction::Down)],
+        };
+
+        assert_eq!(
+            replay.to_text(),
+            "snake-replay 1\nseed 42\ndifficulty 3\nwalls solid\ngoal length 12\n\
+             board 16 8\nsteps 30\nscore 6\nturns 0R 7D\n"
+        );
+    }
+
+    #[test]
+    fn levels_are_written_as_maps() {
+        let map = "#######\n#.....#\n#.<...#\n#.....#\n#######\n";
+        let level = Level::parse("tiny", map).unwrap();
+        let replay = Replay {
+            settings: settings(level),
+            seed: 1,
+            steps: 0,
+            score: 0,
+            turns: vec![],
+        };
+
+        let text = replay.to_text();
+        assert!(text.contains(&format!("map tiny\n{}end\n", map)));
+        assert!(!text.contains("turns"));
+    }
+
+    /// A short game with a few turns, and its recording.
+    fn recorded_game() -> (Game, Replay) {
+        let settings = GameSettings {
+            walls: WallMode::Wrap,
+            goal: None,
+            ..settings(Level::empty((8, 16)))
+        };
+        let turns = [
+            (2, Direction::Right),
+            (5, Direction::Down),
+            (9, Direction::Left),
+            (14, Direction::Down),
+            (20, Direction::Right),
+        ];
+        let original = play(&settings, 1234, 60, &turns);
+        assert_eq!(original.turns(), &turns);
+        let replay = Replay::new(&settings, &original);
+        (original, replay)
+    }
+
+    #[test]
+    fn recorded_turns_rebuild_the_same_game() {
+        let (original, replay) = recorded_game();
+
+        let rebuilt = play(&replay.settings, replay.seed, replay.steps, &replay.turns);
+        assert_eq!(rebuilt.score, replay.score);
+        assert_eq!(rebuilt.steps, replay.steps);
+        assert_eq!(rebuilt.food, original.food);
+        assert_eq!(rebuilt.snake_body(), original.snake_body());
+    }
+
+    #[test]
+    fn playback_of_a_saved_recording_rebuilds_the_same_game() {
+        let (original, replay) = recorded_game();
+        let replay = Replay::parse(&replay.to_text()).unwrap();
+
+        let mut playback = Playback::new(replay);
+        playback.finish();
+        assert!(playback.verify().is_ok());
+        assert_eq!(playback.game.steps, original.steps);
+        assert_eq!(playback.game.food, original.food);
+        assert_eq!(playback.game.snake_body(), original.snake_body());
+    }
+
+    #[test]
+    fn playback_reports_a_different_score() {
+        let (_, mut replay) = recorded_game();
+        replay.score += 1;
+
+        let mut playback = Playback::new(replay);
+        playback.finish();
+        assert!(playback.verify().is_err());
+    }
+
+    #[test]
+    fn parsed_maps_keep_the_level() {
+        let map = "#######\n#.....#\n#.<...#\n#.....#\n#######\n";
+        let replay = Replay {
+            settings: settings(Level::parse("tiny", map).unwrap()),
+            seed: 5,
+            steps: 3,
+            score: 0,
+            turns: vec![(1, Direction::Up)],
+        };
+
+        let parsed = Replay::parse(&replay.to_text()).unwrap();
+        assert_eq!(parsed.settings.level.name.as_deref(), Some("tiny"));
+        assert_eq!(parsed.settings.level.to_map(), map);
+        assert_eq!(parsed.settings.goal, Some(Goal::Length(12)));
+        assert_eq!(parsed.turns, replay.turns);
+    }
+
+    #[test]
+    fn settings_outside_the_command_line_limits_are_rejected() {
+        let error = |line: &str| match Replay::parse(&format!("snake-replay 1\n{}\n", line)) {
+            Err(ReplayError::Parse { message, .. }) => message,
+            _ => panic!("expected a parse error for {:?}", line),
+        };
+
+        assert_eq!(
+            error("board 60000 60000"),
+            "board must be between 3x3 and 255x255"
+        );
+        assert_eq!(error("board 2 8"), "board must be between 3x3 and 255x255");
+        assert_eq!(error("difficulty 0"), "difficulty must be between 1 and 9");
+        assert_eq!(error("difficulty 10"), "difficulty must be between 1 and 9");
+        // The largest board is fine, the replay only misses everything else
+        assert_eq!(error("board 255 255"), "missing difficulty");
+    }
+
+    #[test]
+    fn invalid_recordings_are_rejected() {
+        let error = |text: &str| match Replay::parse(text) {
+            Err(ReplayError::Parse { line, message }) => (line, message),
+            _ => panic!("expected a parse error for {:?}", text),
+        };
+
+        assert_eq!(error("seed 1\n").1, "not a snake replay");
+        assert_eq!(error("snake-replay 2\n").1, "unsupported version 2");
+        assert_eq!(
+            error("snake-replay 1\nseed 1\nturns 3R 4X\n"),
+            (3, String::from("invalid turn '4X'"))
+        );
+        assert_eq!(error("snake-replay 1\nseed 1\n").1, "missing board or map");
+    }
+}
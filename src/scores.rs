@@ -0,0 +1,292 @@
+//! Local high score table.
+//!
+//! Scores are kept in `scores.tsv` in the data directory. Each line is one entry made
+//! of six tab-separated fields:
+//!
+//! ```text
+//! <width> <height> <difficulty> <rules> <score> <name>
+//! ```
+//!
+//! `rules` is the wall mode (`wrap` or `solid`), followed by `:<level name>` when the
+//! game was played on a level file or in the campaign. Only entries with the same
+//! width, height, difficulty and rules are ranked against each other, and at most
+//! [`TABLE_SIZE`] entries are kept for each combination. Lines that cannot be parsed are
+//! skipped.
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use crate::settings::{GameSettings, WallMode};
+use crate::storage;
+
+pub const TABLE_SIZE: usize = 10;
+
+/// Longest player name, longer ones are cut off.
+pub const MAX_NAME_LENGTH: usize = 16;
+
+/// Combination of settings that scores are ranked within.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct ScoreKey {
+    pub width: u16,
+    pub height: u16,
+    pub difficulty: u16,
+    pub rules: String,
+}
+
+impl ScoreKey {
+    pub fn from_settings(settings: &GameSettings) -> ScoreKey {
+        let walls = match settings.walls {
+            WallMode::Solid => "solid",
+            WallMode::Wrap => "wrap",
+        };
+        let rules = match &settings.level.name {
+            Some(name) => format!("{}:{}", walls, name.replace(char::is_whitespace, "_")),
+            None => walls.to_string(),
+        };
+
+        ScoreKey {
+            width: settings.level.dimensions.1,
+            height: settings.level.dimensions.0,
+            difficulty: settings.difficulty,
+            rules,
+        }
+    }
+}
+
+impl fmt::Display for ScoreKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}x{}, difficulty {}, {}",
+            self.width, self.height, self.difficulty, self.rules
+        )
+    }
+}
+
+#[derive(Clone)]
+pub struct ScoreEntry {
+    pub key: ScoreKey,
+    pub score: u32,
+    pub name: String,
+}
+
+pub struct ScoreTable {
+    entries: Vec<ScoreEntry>,
+}
+
+impl ScoreTable {
+    /// Reads the table from disk, an unreadable or missing file gives an empty table.
+    pub fn load() -> ScoreTable {
+        let text = scores_file()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .unwrap_or_default();
+
+        ScoreTable::parse(&text)
+    }
+
+    /// Reads the table from the contents of the scores file.
+    fn parse(text: &str) -> ScoreTable {
+        ScoreTable {
+            entries: text.lines().filter_map(parse_entry).collect(),
+        }
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let Some(path) = scores_file() else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let text: String = self
+            .entries
+            .iter()
+            .map(|e| {
+                format!(
+                    "{}\t{}\t{}\t{}\t{}\t{}\n",
+                    e.key.width, e.key.height, e.key.difficulty, e.key.rules, e.score, e.name
+                )
+            })
+            .collect();
+        fs::write(path, text)
+    }
+
+    /// Entries for `key`, best first.
+    pub fn ranking(&self, key: &ScoreKey) -> Vec<&ScoreEntry> {
+        let mut ranking: Vec<_> = self.entries.iter().filter(|e| &e.key == key).collect();
+        ranking.sort_by_key(|e| Reverse(e.score));
+        ranking.truncate(TABLE_SIZE);
+        ranking
+    }
+
+    pub fn best(&self, key: &ScoreKey) -> Option<u32> {
+        self.ranking(key).first().map(|e| e.score)
+    }
+
+    pub fn is_high_score(&self, key: &ScoreKey, score: u32) -> bool {
+        let ranking = self.ranking(key);
+        score > 0 && (ranking.len() < TABLE_SIZE || ranking.iter().any(|e| score > e.score))
+    }
+
+    pub fn insert(&mut self, key: ScoreKey, score: u32, name: &str) {
+        self.entries.push(ScoreEntry {
+            key: key.clone(),
+            score,
+            name: clean_name(name),
+        });
+
+        let kept: Vec<ScoreEntry> = self.ranking(&key).into_iter().cloned().collect();
+        self.entries.retain(|e| e.key != key);
+        self.entries.extend(kept);
+    }
+
+    /// All keys that have at least one entry, sorted.
+    pub fn keys(&self) -> Vec<ScoreKey> {
+        let mut keys: Vec<ScoreKey> = self.entries.iter().map(|e| e.key.clone()).collect();
+        keys.sort();
+        keys.dedup();
+        keys
+    }
+}
+
+fn scores_file() -> Option<PathBuf> {
+    storage::data_dir().map(|dir| dir.join("scores.tsv"))
+}
+
+fn parse_entry(line: &str) -> Option<ScoreEntry> {
+    let fields: Vec<&str> = line.splitn(6, '\t').collect();
+    let [width, height, difficulty, rules, score, name] = fields[..] else {
+        return None;
+    };
+
+    Some(ScoreEntry {
+        key: ScoreKey {
+            width: width.parse().ok()?,
+            height: height.parse().ok()?,
+            difficulty: difficulty.parse().ok()?,
+            rules: rules.to_string(),
+        },
+        score: score.parse().ok()?,
+        name: name.to_string(),
+    })
+}
+
+/// The name without control characters, which could mess up the terminals and the file
+/// it ends up in, and cut off at [`MAX_NAME_LENGTH`]. An empty name becomes `anonymous`.
+pub fn clean_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .take(MAX_NAME_LENGTH)
+        .collect();
+    match name.trim() {
+        "" => "anonymous".to_string(),
+        name => name.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(rules: &str) -> ScoreKey {
+        ScoreKey {
+            width: 16,
+            height: 8,
+            difficulty: 5,
+            rules: rules.to_string(),
+        }
+    }
+
+    fn names(table: &ScoreTable, key: &ScoreKey) -> Vec<(u32, String)> {
+        table
+            .ranking(key)
+            .iter()
+            .map(|e| (e.score, e.name.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn malformed_lines_are_skipped() {
+        let table = ScoreTable::parse(
+            "16\t8\t5\twrap\t40\tada\n\
+             16\t8\t5\twrap\tlots\tbob\n\
+             16\t8\twrap\t30\n\
+             \n\
+             16\t8\t5\twrap\t20\tcy with\ttab\n",
+        );
+
+        assert_eq!(
+            names(&table, &key("wrap")),
+            vec![(40, "ada".to_string()), (20, "cy with\ttab".to_string())]
+        );
+    }
+
+    #[test]
+    fn entries_are_ranked_best_first() {
+        let mut table = ScoreTable::parse("");
+        table.insert(key("wrap"), 20, "ada");
+        table.insert(key("wrap"), 50, "bob");
+        table.insert(key("wrap"), 35, "cy");
+
+        assert_eq!(
+            names(&table, &key("wrap")),
+            vec![
+                (50, "bob".to_string()),
+                (35, "cy".to_string()),
+                (20, "ada".to_string())
+            ]
+        );
+        assert_eq!(table.best(&key("wrap")), Some(50));
+    }
+
+    #[test]
+    fn only_the_best_entries_are_kept() {
+        let mut table = ScoreTable::parse("");
+        for score in 1..=TABLE_SIZE as u32 {
+            table.insert(key("wrap"), score * 10, "ada");
+        }
+
+        assert!(!table.is_high_score(&key("wrap"), 10));
+        assert!(table.is_high_score(&key("wrap"), 11));
+        table.insert(key("wrap"), 11, "bob");
+
+        let ranking = names(&table, &key("wrap"));
+        assert_eq!(ranking.len(), TABLE_SIZE);
+        assert_eq!(ranking.last().unwrap(), &(11, "bob".to_string()));
+        assert_eq!(table.entries.len(), TABLE_SIZE);
+    }
+
+    #[test]
+    fn settings_are_ranked_separately() {
+        let mut table = ScoreTable::parse("");
+        for score in 1..=TABLE_SIZE as u32 {
+            table.insert(key("wrap"), score * 10, "ada");
+        }
+        table.insert(key("solid"), 5, "bob");
+
+        assert_eq!(table.best(&key("solid")), Some(5));
+        assert!(table.is_high_score(&key("solid"), 1));
+        assert!(!table.is_high_score(&key("solid"), 0));
+        assert_eq!(table.keys(), vec![key("solid"), key("wrap")]);
+    }
+
+    #[test]
+    fn names_are_cleaned_up() {
+        let mut table = ScoreTable::parse("");
+        table.insert(key("wrap"), 10, "a\tvery long name that goes on");
+        table.insert(key("wrap"), 5, "  ");
+
+        assert_eq!(
+            names(&table, &key("wrap")),
+            vec![
+                (10, "a very long name".to_string()),
+                (5, "anonymous".to_string())
+            ]
+        );
+    }
+}
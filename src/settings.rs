@@ -0,0 +1,57 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::level::Level;
+
+/// What happens when the snake reaches the edge of the board.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WallMode {
+    /// Hitting the border ends the game
+    Solid,
+    /// The snake comes out on the opposite side
+    Wrap,
+}
+
+/// What the player has to reach to clear a level.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Goal {
+    Length(u16),
+    Score(u32),
+}
+
+impl fmt::Display for Goal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Goal::Length(length) => write!(f, "length {}", length),
+            Goal::Score(score) => write!(f, "{} points", score),
+        }
+    }
+}
+
+/// Everything needed to set up a new game.
+#[derive(Clone)]
+pub struct GameSettings {
+    pub level: Level,
+    pub difficulty: u16,
+    pub walls: WallMode,
+    /// Ends the game as soon as it is reached, endless game if `None`
+    pub goal: Option<Goal>,
+    /// A second player steers another snake on the same board
+    pub versus: bool,
+}
+
+#[cfg(test)]
+impl GameSettings {
+    /// An endless single-player game at difficulty 5, the common setup of the tests.
+    pub fn for_test(level: Level, walls: WallMode) -> GameSettings {
+        GameSettings {
+            level,
+            difficulty: 5,
+            walls,
+            goal: None,
+            versus: false,
+        }
+    }
+}
@@ -1,9 +1,13 @@
+use std::collections::VecDeque;
+
 use crate::direction::Direction;
 
 pub struct Snake {
     pub body: Vec<(u16, u16)>,
     pub direction: Direction,
-    next_direction: Option<Direction>,
+    /// Turns waiting to be taken, one per move
+    queued_directions: VecDeque<Direction>,
+    input_buffer: usize,
 }
 
 impl Snake {
@@ -11,32 +15,132 @@ impl Snake {
         Snake {
             body,
             direction,
-            next_direction: None,
+            queued_directions: VecDeque::new(),
+            input_buffer: 1,
         }
     }
 
+    /// Sets how many turns can be queued ahead of the snake's moves.
+    pub fn set_input_buffer(&mut self, size: usize) {
+        self.input_buffer = size.max(1);
+        self.queued_directions.truncate(self.input_buffer);
+    }
+
+    /// Queues a turn. It is ignored if the buffer is full, or if it would not change the
+    /// direction the snake has after all turns queued before it.
     pub fn change_direction(&mut self, direction: Direction) {
+        if self.queued_directions.len() >= self.input_buffer {
+            return;
+        }
+
+        let previous = *self.queued_directions.back().unwrap_or(&self.direction);
         match direction {
-            Direction::Up | Direction::Down => match self.direction {
-                Direction::Left | Direction::Right => self.next_direction = Some(direction),
+            Direction::Up | Direction::Down => match previous {
+                Direction::Left | Direction::Right => self.queued_directions.push_back(direction),
                 _ => {}
             },
-            Direction::Left | Direction::Right => match self.direction {
-                Direction::Up | Direction::Down => self.next_direction = Some(direction),
+            Direction::Left | Direction::Right => match previous {
+                Direction::Up | Direction::Down => self.queued_directions.push_back(direction),
                 _ => {}
             },
         }
     }
 
+    /// Moves the head to `head` and the rest of the body after it. With `grow`, the tail
+    /// stays where it is.
+    pub fn advance(&mut self, head: (u16, u16), grow: bool) {
+        if !grow {
+            self.body.pop();
+        }
+        self.body.insert(0, head);
+    }
+
     pub fn next_direction(&mut self) -> Direction {
-        match self.next_direction {
-            Some(direction) => {
-                self.direction = direction;
-                self.next_direction = None;
-            }
-            None => {}
+        if let Some(direction) = self.queued_directions.pop_front() {
+            self.direction = direction;
         }
 
         self.direction
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snake() -> Snake {
+        Snake::new(vec![(1, 3), (1, 2), (1, 1)], Direction::Right)
+    }
+
+    fn moves(snake: &mut Snake, count: usize) -> Vec<Direction> {
+        (0..count).map(|_| snake.next_direction()).collect()
+    }
+
+    #[test]
+    fn turns_are_taken_in_order_one_per_move() {
+        let mut snake = snake();
+        snake.set_input_buffer(3);
+        snake.change_direction(Direction::Up);
+        snake.change_direction(Direction::Left);
+        snake.change_direction(Direction::Down);
+
+        assert_eq!(
+            moves(&mut snake, 4),
+            vec![
+                Direction::Up,
+                Direction::Left,
+                Direction::Down,
+                Direction::Down
+            ]
+        );
+    }
+
+    #[test]
+    fn turns_are_checked_against_the_last_queued_direction() {
+        let mut snake = snake();
+        snake.set_input_buffer(3);
+        // Reverses the snake's direction
+        snake.change_direction(Direction::Left);
+        snake.change_direction(Direction::Up);
+        // Reverse the queued turn, or go on in its direction
+        snake.change_direction(Direction::Down);
+        snake.change_direction(Direction::Up);
+        snake.change_direction(Direction::Right);
+
+        assert_eq!(
+            moves(&mut snake, 3),
+            vec![Direction::Up, Direction::Right, Direction::Right]
+        );
+    }
+
+    #[test]
+    fn turns_beyond_the_buffer_are_dropped() {
+        let mut snake = snake();
+        snake.change_direction(Direction::Up);
+        snake.change_direction(Direction::Left);
+
+        assert_eq!(moves(&mut snake, 2), vec![Direction::Up, Direction::Up]);
+
+        snake.set_input_buffer(2);
+        snake.change_direction(Direction::Left);
+        snake.change_direction(Direction::Down);
+        snake.change_direction(Direction::Right);
+
+        assert_eq!(
+            moves(&mut snake, 3),
+            vec![Direction::Left, Direction::Down, Direction::Down]
+        );
+    }
+
+    #[test]
+    fn shrinking_the_buffer_drops_the_latest_turns() {
+        let mut snake = snake();
+        snake.set_input_buffer(3);
+        snake.change_direction(Direction::Up);
+        snake.change_direction(Direction::Left);
+        snake.change_direction(Direction::Down);
+        snake.set_input_buffer(0);
+
+        assert_eq!(moves(&mut snake, 2), vec![Direction::Up, Direction::Up]);
+    }
+}
@@ -0,0 +1,23 @@
+use std::env;
+use std::path::PathBuf;
+
+/// Directory for persistent game data, following the XDG base directory spec
+/// (`$XDG_DATA_HOME/snake-rs`, falling back to `~/.local/share/snake-rs`).
+pub fn data_dir() -> Option<PathBuf> {
+    xdg_dir("XDG_DATA_HOME", ".local/share")
+}
+
+/// Directory for the config file (`$XDG_CONFIG_HOME/snake-rs`, falling back to
+/// `~/.config/snake-rs`).
+pub fn config_dir() -> Option<PathBuf> {
+    xdg_dir("XDG_CONFIG_HOME", ".config")
+}
+
+fn xdg_dir(variable: &str, default_in_home: &str) -> Option<PathBuf> {
+    let base = env::var_os(variable)
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(default_in_home)))?;
+
+    Some(base.join("snake-rs"))
+}
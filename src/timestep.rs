@@ -0,0 +1,92 @@
+use std::time::Duration;
+
+/// Most steps a single frame can catch up on. Anything beyond that, e.g. after the
+/// terminal was suspended, is dropped instead of fast-forwarding the game.
+const MAX_CATCH_UP_STEPS: u32 = 5;
+
+/// Turns frame times of any length into logical steps of a fixed length, carrying
+/// leftover time over to the next frame.
+pub struct FixedTimestep {
+    step: Duration,
+    accumulator: Duration,
+}
+
+impl FixedTimestep {
+    pub fn new(step: Duration) -> FixedTimestep {
+        FixedTimestep {
+            step,
+            accumulator: Duration::ZERO,
+        }
+    }
+
+    pub fn step(&self) -> Duration {
+        self.step
+    }
+
+    /// Adds the time a frame took and returns how many steps are due.
+    pub fn advance(&mut self, delta: Duration) -> u32 {
+        self.accumulator += delta.min(self.step * MAX_CATCH_UP_STEPS);
+
+        let mut steps = 0;
+        while self.accumulator >= self.step {
+            self.accumulator -= self.step;
+            steps += 1;
+        }
+
+        steps
+    }
+
+    /// Time left until the next step is due.
+    pub fn until_next_step(&self) -> Duration {
+        self.step.saturating_sub(self.accumulator)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leftover_time_is_carried_over() {
+        let mut timestep = FixedTimestep::new(Duration::from_millis(100));
+
+        assert_eq!(timestep.advance(Duration::from_millis(60)), 0);
+        assert_eq!(timestep.advance(Duration::from_millis(60)), 1);
+        assert_eq!(timestep.advance(Duration::from_millis(70)), 0);
+        assert_eq!(timestep.advance(Duration::from_millis(10)), 1);
+    }
+
+    #[test]
+    fn time_until_next_step_counts_the_leftover_time() {
+        let mut timestep = FixedTimestep::new(Duration::from_millis(100));
+
+        assert_eq!(timestep.until_next_step(), Duration::from_millis(100));
+        assert_eq!(timestep.advance(Duration::from_millis(60)), 0);
+        assert_eq!(timestep.until_next_step(), Duration::from_millis(40));
+        assert_eq!(timestep.advance(Duration::from_millis(60)), 1);
+        assert_eq!(timestep.until_next_step(), Duration::from_millis(80));
+        assert_eq!(timestep.advance(Duration::from_millis(80)), 1);
+        assert_eq!(timestep.until_next_step(), Duration::from_millis(100));
+    }
+
+    #[test]
+    fn low_frame_rate_runs_several_steps_per_frame() {
+        let mut timestep = FixedTimestep::new(Duration::from_millis(20));
+        let frame = Duration::from_millis(50);
+
+        let steps: u32 = (0..20).map(|_| timestep.advance(frame)).sum();
+
+        assert_eq!(steps, 50);
+    }
+
+    #[test]
+    fn catch_up_after_a_stall_is_capped() {
+        let mut timestep = FixedTimestep::new(Duration::from_millis(100));
+
+        assert_eq!(
+            timestep.advance(Duration::from_secs(60)),
+            MAX_CATCH_UP_STEPS
+        );
+        assert_eq!(timestep.advance(Duration::from_millis(100)), 1);
+    }
+}